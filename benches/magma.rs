@@ -0,0 +1,118 @@
+//! Compares the precomputed round function with the one computed directly:
+//! `cargo bench --bench magma`
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use GOST_collision::bitslice::{BitslicedMagma, Lanes};
+use GOST_collision::magma::{utils, Block, HalfBlock, Magma, ParamSet, RoundKey, RoundTable};
+use GOST_collision::width::Width;
+
+static KEY: u64 = 0x1b1b_1b1b_e4e4_e4e4;
+
+/// A round, which computes the S-boxes and the rotation every time, as `Magma::round` used to
+fn direct_round(params: &ParamSet, left: &mut HalfBlock, right: &mut HalfBlock, key: RoundKey) {
+    let x = right.wrapping_add(key) & Width::<2>::HALF_BLOCK_MASK;
+    *left ^= utils::g::<2>(params, x);
+    std::mem::swap(left, right);
+}
+
+/// The round keys sk0..sk7 three times, then sk7..sk0
+fn round_keys() -> Vec<RoundKey> {
+    let sk = |i: usize| ((KEY >> (8 * i)) & 0xff) as RoundKey;
+    (0..24).map(|i| sk(i % 8)).chain((0..8).map(|i| sk(7 - i))).collect()
+}
+
+fn encrypt_block(c: &mut Criterion) {
+    let params = ParamSet::Toy;
+    let keys = round_keys();
+    let magma = Magma::<2>::with_params(KEY, params);
+
+    let direct = |block: Block| {
+        let (mut left, mut right) = ((block & 0xff) as HalfBlock, (block >> 8) as HalfBlock);
+        for key in keys.iter() {
+            direct_round(&params, &mut left, &mut right, *key);
+        }
+        ((left as Block) << 8) | right as Block
+    };
+    assert_eq!(direct(0x6c1e), magma.encrypt_block(0x6c1e));
+
+    let mut group = c.benchmark_group("encrypt_block");
+    group.bench_function("direct", |b| b.iter(|| direct(black_box(0x6c1e))));
+    group.bench_function("table", |b| b.iter(|| magma.encrypt_block(black_box(0x6c1e))));
+    // 128 blocks a time, so the time is per 128 blocks
+    let bitsliced = BitslicedMagma::<2>::new(&params);
+    let (keys, blocks) = (vec![KEY; 128], vec![0x6c1e; 128]);
+    group.bench_function("bitsliced x128", |b| {
+        b.iter(|| bitsliced.encrypt_blocks::<u128>(black_box(&keys), black_box(&blocks)))
+    });
+    group.finish();
+}
+
+/// The inner loop of a seeker: 4 rounds for every half-key of sk0..sk3
+fn seekers(c: &mut Criterion) {
+    let params = ParamSet::Toy;
+    let table = RoundTable::<2>::new(&params);
+    let half_keys = 0..1u32 << 16;
+    let round_key = |half_key: u32, i: u32| (half_key >> (8 * i)) & 0xff;
+
+    let mut group = c.benchmark_group("seekers");
+    group.bench_function("direct", |b| {
+        b.iter(|| {
+            half_keys.clone().fold(0, |acc, half_key| {
+                let (mut left, mut right) = black_box((0x1e, 0x6c));
+                for i in 0..4 {
+                    direct_round(&params, &mut left, &mut right, round_key(half_key, i));
+                }
+                acc ^ left ^ right
+            })
+        })
+    });
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            half_keys.clone().fold(0, |acc, half_key| {
+                let (mut left, mut right) = black_box((0x1e, 0x6c));
+                for i in 0..4 {
+                    Magma::<2>::round(&table, &mut left, &mut right, round_key(half_key, i));
+                }
+                acc ^ left ^ right
+            })
+        })
+    });
+    let bitsliced = BitslicedMagma::<2>::new(&params);
+    group.bench_function("bitsliced", |b| {
+        b.iter(|| {
+            let mut acc = 0;
+            for batch in (0..1u32 << 16).step_by(128) {
+                let keys: Vec<Lanes<2, u128>> =
+                    (0..4).map(|i| Lanes::pack((batch..batch + 128).map(|k| round_key(k, i)))).collect();
+                let (mut left, mut right) = (Lanes::splat(black_box(0x1e)), Lanes::splat(black_box(0x6c)));
+                bitsliced.rounds(&mut left, &mut right, &keys);
+                for i in 0..128 {
+                    acc ^= left.get(i) ^ right.get(i);
+                }
+            }
+            acc
+        })
+    });
+    // The same calls as `seek_forward` and `seek_backward` make: the half-key goes into sk0..sk3
+    // or sk4..sk7 of the key, the rest of it is zero
+    let forward_keys: Vec<u64> = (0..1u64 << 16).collect();
+    let backward_keys: Vec<u64> = forward_keys.iter().map(|k| k << 32).collect();
+    let blocks = vec![0x6c1e; 1 << 16];
+    assert_eq!(
+        bitsliced.encrypt_rounds::<u128>(&forward_keys[..1000], &blocks[..1000], 0..4)[999],
+        Magma::<2>::with_params(999, params).encrypt_rounds(0x6c1e, 0..4)
+    );
+    assert_eq!(
+        bitsliced.decrypt_rounds::<u128>(&backward_keys[..1000], &blocks[..1000], 4..8)[999],
+        Magma::<2>::with_params(999 << 32, params).decrypt_rounds(0x6c1e, 4..8)
+    );
+    group.bench_function("bitsliced forward", |b| {
+        b.iter(|| bitsliced.encrypt_rounds::<u128>(&forward_keys, black_box(&blocks), 0..4))
+    });
+    group.bench_function("bitsliced backward", |b| {
+        b.iter(|| bitsliced.decrypt_rounds::<u128>(&backward_keys, black_box(&blocks), 4..8))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encrypt_block, seekers);
+criterion_main!(benches);
@@ -0,0 +1,152 @@
+//! The parsing of the command line, which is shared by all the commands
+
+use GOST_collision::error::{GostError, Result};
+use GOST_collision::format::{self, Format};
+use GOST_collision::gost_collision::utils;
+use GOST_collision::magma::{Block, ParamSet};
+use GOST_collision::width::{State, Storage, Width};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+/// The options and the positional arguments of a command
+pub struct Options {
+    values: HashMap<String, String>,
+    positional: Vec<String>,
+    pub help: bool,
+}
+
+impl Options {
+    /// `with_value` lists the options, which take a value. Any other option is an error.
+    pub fn parse<I: Iterator<Item = String>>(mut args: I, with_value: &[&str]) -> Result<Options> {
+        let mut res = Options {
+            values: HashMap::new(),
+            positional: Vec::new(),
+            help: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--help" | "-h" => res.help = true,
+                _ if with_value.contains(&arg.as_str()) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| GostError::Parse(format!("{} requires a value", arg)))?;
+                    res.values.insert(arg, value);
+                }
+                _ if arg.starts_with("--") => return Err(GostError::Parse(format!("Unknown option {:?}", arg))),
+                _ => res.positional.push(arg),
+            }
+        }
+
+        Ok(res)
+    }
+
+    pub fn value(&self, option: &str) -> Option<&str> {
+        self.values.get(option).map(|v| v.as_str())
+    }
+
+    pub fn number<T: FromStr>(&self, option: &str) -> Result<Option<T>> {
+        self.value(option)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| GostError::Parse(format!("{} should be a number, got {:?}", option, v)))
+            })
+            .transpose()
+    }
+
+    /// The width of a "byte", `--width`
+    pub fn width(&self) -> Result<u32> {
+        Ok(self.number("--width")?.unwrap_or(2))
+    }
+
+    /// The S-box parameter set, `--sbox`
+    pub fn params(&self) -> Result<ParamSet> {
+        self.value("--sbox").map_or(Ok(ParamSet::Toy), |name| name.parse())
+    }
+
+    /// The format of the inputs, `--format`, it is guessed by default
+    pub fn format(&self) -> Result<Option<Format>> {
+        self.value("--format").map(|name| name.parse()).transpose()
+    }
+
+    /// Parses the "bytes" of the argument, `len` is the expected number of them, if it is known.
+    /// `-` reads the stdin and `@<file>` reads the file.
+    pub fn bytes<const W: u32>(&self, arg: &str, len: Option<usize>) -> Result<Vec<u8>> {
+        let input = match arg.strip_prefix('@') {
+            Some(path) => fs::read_to_string(path)?,
+            None if arg == "-" => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                input
+            }
+            None => arg.to_string(),
+        };
+
+        format::parse::<W>(&input, self.format()?, len)
+    }
+
+    /// Parses 32 "bytes" of `W` bits, a state, a key or a message block
+    pub fn state<const W: u32>(&self, arg: &str) -> Result<State<W>>
+    where
+        Width<W>: Storage,
+    {
+        let bytes = self.bytes::<W>(arg, Some(32))?;
+        if bytes.len() != 32 {
+            return Err(GostError::WrongLength(bytes.len()));
+        }
+
+        Ok(utils::pack::<W, _>(&bytes))
+    }
+
+    /// Parses a block of Magma, which is 8 "bytes" of `W` bits
+    pub fn block<const W: u32>(&self, arg: &str) -> Result<Block> {
+        let bytes = self.bytes::<W>(arg, Some(8))?;
+        if bytes.len() != 8 {
+            return Err(GostError::Parse(format!("The block should be 8 bytes long, got {}", bytes.len())));
+        }
+
+        Ok(utils::pack::<W, _>(&bytes))
+    }
+
+    /// Returns exactly `names.len()` positional arguments, `names` are used in the error message
+    pub fn positional(&self, names: &[&str]) -> Result<&[String]> {
+        if self.positional.len() != names.len() {
+            return Err(GostError::Parse(format!(
+                "Expected {} argument(s): {}, got {}",
+                names.len(),
+                names.join(", "),
+                self.positional.len()
+            )));
+        }
+
+        Ok(&self.positional)
+    }
+
+    /// Returns at most `max` positional arguments
+    pub fn positional_up_to(&self, max: usize) -> Result<&[String]> {
+        match self.positional.get(max) {
+            Some(arg) => Err(GostError::Parse(format!("Unexpected argument {:?}", arg))),
+            None => Ok(&self.positional),
+        }
+    }
+}
+
+/// Formats the state the way it is parsed by default
+pub fn format_state<const W: u32>(x: State<W>) -> String
+where
+    Width<W>: Storage,
+{
+    let mut bytes = [0u8; 32];
+    utils::unpack::<W, _>(&mut bytes, x);
+
+    Format::Decimal.format::<W>(&bytes)
+}
+
+pub fn format_block<const W: u32>(x: Block) -> String {
+    let mut bytes = [0u8; 8];
+    utils::unpack::<W, _>(&mut bytes, x);
+
+    Format::Decimal.format::<W>(&bytes)
+}
@@ -0,0 +1,210 @@
+//! The collision attack over `GostAttack`, which takes a state with the symmetric first quarter.
+//! The progress is drawn with indicatif.
+
+use crate::args::Options;
+use crate::commands::Help;
+use crate::output::{Found, Output};
+use GOST_collision::budget::{Budget, CancelToken};
+use GOST_collision::checkpoint;
+use GOST_collision::error::{GostError, Result};
+use GOST_collision::gost_collision::{GostAttack, Outcome};
+use GOST_collision::progress::{Phase, ProgressSink};
+use GOST_collision::width::{Storage, Width};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Cancels the running attack, used by the SIGINT handler
+static INTERRUPT: OnceLock<CancelToken> = OnceLock::new();
+
+pub static OPTIONS: &[&str] = &[
+    "--width",
+    "--sbox",
+    "--format",
+    "--seed",
+    "--checkpoint",
+    "--resume",
+    "--timeout",
+    "--max-fixed-points",
+    "--threads",
+    "--output",
+    "--certificate",
+];
+pub static HELP: Help = Help {
+    usage: "Usage: gost attack [options] (--resume <file> | \"<state>\")
+
+Finds two message blocks, which give the same output of the compression function
+for the state of 32 \"bytes\" with the symmetric first quarter.",
+    options: "    --seed <n>      Makes the run reproducible, the seed is printed along with the collision
+    --checkpoint <file>
+                    Saves the progress every minute and on Ctrl-C
+    --resume <file> Continues from a saved checkpoint instead of taking a state,
+                    the width, the seed and the S-boxes come from the checkpoint
+    --timeout <seconds>
+                    Stops the attack, when it takes too long
+    --max-fixed-points <n>
+                    Stops the attack, when it keeps too many fixed points
+    --threads <n>   The number of threads searching for the keys, one per CPU by default
+    --output <name> Prints the collision along with the details of the attack as text,
+                    hex or json (text by default)
+    --certificate <file>
+                    Writes the collision into the file, see `gost verify`
+",
+};
+
+/// Draws the progress of the attack: the fixed points and the keys of the running key search
+#[derive(Default)]
+struct BarProgress {
+    fixed_points: Mutex<Option<ProgressBar>>,
+    keys: Mutex<Option<ProgressBar>>,
+}
+
+impl BarProgress {
+    fn bar(&self, phase: Phase) -> &Mutex<Option<ProgressBar>> {
+        match phase {
+            Phase::FixedPoints => &self.fixed_points,
+            _ => &self.keys,
+        }
+    }
+
+    fn with_bar<F: FnOnce(&ProgressBar)>(&self, phase: Phase, f: F) {
+        if let Some(pb) = self.bar(phase).lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            f(pb);
+        }
+    }
+}
+
+impl ProgressSink for BarProgress {
+    fn phase_start(&self, phase: Phase, total: u64) {
+        let pb = ProgressBar::new(total);
+        let (template, msg) = match phase {
+            Phase::FixedPoints => ("[{bar:50.green/green}] {pos}/{len} {msg}", "Fixed Points"),
+            Phase::Forward => ("[{elapsed_precise}] [{bar:50.cyan/blue}] {pos}/{len} {msg}", "Keys probed"),
+            Phase::Backward => (
+                "[{elapsed_precise}] [{bar:50.cyan/blue}] {pos}/{len} {msg}",
+                "Keys probed backwards",
+            ),
+        };
+        pb.set_style(ProgressStyle::default_bar().template(template).progress_chars("#>-"));
+        pb.set_message(msg);
+        *self.bar(phase).lock().unwrap_or_else(|e| e.into_inner()) = Some(pb);
+    }
+
+    fn phase_end(&self, phase: Phase) {
+        if let Some(pb) = self.bar(phase).lock().unwrap_or_else(|e| e.into_inner()).take() {
+            pb.finish_and_clear();
+        }
+    }
+
+    fn keys_probed(&self, phase: Phase, n: u64) {
+        self.with_bar(phase, |pb| pb.inc(n));
+    }
+
+    fn fixed_points_found(&self, total: u64) {
+        self.with_bar(Phase::FixedPoints, |pb| pb.set_position(total));
+    }
+}
+
+/// The width of the checkpoint, if the attack is resumed, otherwise `--width`
+pub fn attack_width(opts: &Options) -> Result<u32> {
+    match opts.value("--resume") {
+        Some(path) => checkpoint::width(path),
+        None => opts.width(),
+    }
+}
+
+pub fn attack<const W: u32>(opts: &Options) -> Result<()>
+where
+    Width<W>: Storage,
+{
+    let output: Output = opts.value("--output").map_or(Ok(Output::Text), |name| name.parse())?;
+    let resume = opts.value("--resume");
+    let mut attacker = match (resume, opts.positional_up_to(1)?.first()) {
+        (Some(path), None) => {
+            // All of them come from the checkpoint
+            if let Some(option) = ["--width", "--seed", "--sbox"].iter().find(|o| opts.value(o).is_some()) {
+                return Err(GostError::Parse(format!(
+                    "{} cannot be given with --resume, the checkpoint has it",
+                    option
+                )));
+            }
+            GostAttack::<W>::resume(path)?
+        }
+        (None, Some(h)) => {
+            let h = opts.bytes::<W>(h, Some(32))?;
+            match opts.number("--seed")? {
+                Some(seed) => GostAttack::<W>::try_with_seed(&h, opts.params()?, seed)?,
+                None => GostAttack::<W>::try_with_params(&h, opts.params()?)?,
+            }
+        }
+        (Some(_), Some(_)) => {
+            return Err(GostError::Parse("Either the state or --resume should be given, not both".to_string()))
+        }
+        (None, None) => return Err(GostError::Parse("State block should be provided".to_string())),
+    };
+    // Resuming keeps writing into the same file, unless another one is given
+    if let Some(path) = opts.value("--checkpoint").or(resume) {
+        attacker.set_checkpoint(path, CHECKPOINT_INTERVAL);
+    }
+    if let Some(threads) = opts.number("--threads")? {
+        attacker.set_threads(threads);
+    }
+    attacker.set_progress(Arc::new(BarProgress::default()));
+    attacker.set_budget(Budget {
+        cancel: INTERRUPT.get_or_init(Default::default).clone(),
+        deadline: opts.number("--timeout")?.map(|t| Instant::now() + Duration::from_secs(t)),
+        max_fixed_points: opts.number("--max-fixed-points")?,
+    });
+    handle_sigint();
+
+    let start = Instant::now();
+    let collision = match attacker.generate_collision()? {
+        Outcome::Collision(c) => c,
+        Outcome::Partial(p) => {
+            eprintln!(
+                "{} fixed points after {} rounds and {} restarts",
+                p.fixed_points.len(),
+                p.rounds,
+                p.restarts
+            );
+            return Err(GostError::Stopped(p.reason));
+        }
+    };
+
+    if let Some(path) = opts.value("--certificate") {
+        collision.save(path)?;
+    }
+    Found::<W> {
+        collision,
+        d: attacker.d(),
+        seed: attacker.seed(),
+        rounds: attacker.rounds(),
+        restarts: attacker.restarts(),
+        elapsed: start.elapsed(),
+    }
+    .print(output);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn handle_sigint() {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        if let Some(token) = INTERRUPT.get() {
+            token.cancel();
+        }
+        // The second Ctrl-C kills the process
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+
+    unsafe {
+        libc::signal(libc::SIGINT, on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+fn handle_sigint() {}
@@ -0,0 +1,165 @@
+//! The commands over the hash function and Magma
+
+use crate::args::{self, Options};
+use GOST_collision::certificate;
+use GOST_collision::error::Result;
+use GOST_collision::gost_collision::Collision;
+use GOST_collision::gost_hash::GostHash;
+use GOST_collision::magma::{Block, Magma};
+use GOST_collision::width::{State, Storage, Width};
+use std::fs;
+
+/// The options of every command, which take a value
+pub static COMMON_OPTIONS: &[&str] = &["--width", "--sbox", "--format"];
+
+static COMMON_HELP: &str = "    --width <bits>  The width of a \"byte\": 1, 2, 4 or 8 (2 by default)
+    --sbox <name>   The S-box parameter set: toy, gost2015, cryptopro-a, cryptopro-b, cryptopro-c,
+                    cryptopro-d or test (toy by default)
+    --format <name> The format of the inputs, guessed by default:
+                    decimal  \"bytes\" separated by spaces, e.g. \"1 2 3 0 ...\"
+                    hex      the packed word, the first \"byte\" is the lowest, e.g. 0x1b1be4e4...
+                    binary   the packed word, e.g. 0b0110...
+                    digits   a digit of base 2^W per \"byte\", e.g. 32 base-4 digits 1230...
+                    Any input can be read from a file with @<file> or from the stdin with -
+    -h, --help      Prints this message";
+
+/// The help of a command: the usage, the description and the options of its own
+pub struct Help {
+    pub usage: &'static str,
+    pub options: &'static str,
+}
+
+impl Help {
+    /// Prints the help along with the common options
+    pub fn print(&self) {
+        println!("{}\n\nOptions:\n{}{}", self.usage, self.options, COMMON_HELP);
+    }
+}
+
+pub static HASH_OPTIONS: &[&str] = &["--width", "--sbox", "--format", "--iv"];
+pub static HASH_HELP: Help = Help {
+    usage: "Usage: gost hash [options] [\"<message>\"]
+
+Hashes the message of any number of \"bytes\" and prints the digest.",
+    options: "    --iv \"<state>\"  The initial state, zero by default\n",
+};
+
+pub static COMPRESS_HELP: Help = Help {
+    usage: "Usage: gost compress [options] \"<state>\" \"<message>\"
+
+Applies the compression function to the state and the message block of 32 \"bytes\" each
+and prints the next state.",
+    options: "",
+};
+
+pub static CIPHER_HELP: Help = Help {
+    usage: "Usage: gost (encrypt | decrypt) [options] \"<key>\" \"<block>\"
+
+Encrypts or decrypts the block of 8 \"bytes\" with the key of 32 \"bytes\".",
+    options: "",
+};
+
+pub static VERIFY_HELP: Help = Help {
+    usage: "Usage: gost verify [options] (<certificate> | \"<state>\" \"<message>\" \"<message>\")
+
+Checks that the messages differ, give the same output of the compression function and
+the first Magma keeps the first block of the state for both of them. The certificate
+written by `gost attack --certificate` holds the width and the S-boxes, so the options
+are ignored for it. Exits with 1, if the check fails.",
+    options: "",
+};
+
+pub fn hash<const W: u32>(opts: &Options) -> Result<()>
+where
+    Width<W>: Storage,
+{
+    let message = match opts.positional_up_to(1)?.first() {
+        Some(arg) => opts.bytes::<W>(arg, None)?,
+        None => Vec::new(),
+    };
+    let iv = match opts.value("--iv") {
+        Some(arg) => opts.state::<W>(arg)?,
+        None => State::<W>::default(),
+    };
+
+    let mut hasher = GostHash::<W>::with_params(iv, opts.params()?);
+    hasher.update(&message);
+    println!("{}", args::format_state::<W>(hasher.finalize()));
+
+    Ok(())
+}
+
+pub fn compress<const W: u32>(opts: &Options) -> Result<()>
+where
+    Width<W>: Storage,
+{
+    let positional = opts.positional(&["state", "message"])?;
+    let h = opts.state::<W>(&positional[0])?;
+    let m = opts.state::<W>(&positional[1])?;
+
+    println!("{}", args::format_state::<W>(GostHash::<W>::compress_with(&opts.params()?, h, m)));
+
+    Ok(())
+}
+
+pub fn encrypt<const W: u32>(opts: &Options) -> Result<()>
+where
+    Width<W>: Storage,
+{
+    let (magma, block) = cipher::<W>(opts)?;
+    println!("{}", args::format_block::<W>(magma.encrypt_block(block)));
+
+    Ok(())
+}
+
+pub fn decrypt<const W: u32>(opts: &Options) -> Result<()>
+where
+    Width<W>: Storage,
+{
+    let (magma, block) = cipher::<W>(opts)?;
+    println!("{}", args::format_block::<W>(magma.decrypt_block(block)));
+
+    Ok(())
+}
+
+fn cipher<const W: u32>(opts: &Options) -> Result<(Magma<W>, Block)>
+where
+    Width<W>: Storage,
+{
+    let positional = opts.positional(&["key", "block"])?;
+    let key = opts.state::<W>(&positional[0])?;
+    let block = opts.block::<W>(&positional[1])?;
+
+    Ok((Magma::<W>::with_params(key, opts.params()?), block))
+}
+
+/// The width of the certificate, if one is given, otherwise `--width`
+pub fn verify_width(opts: &Options) -> Result<u32> {
+    match opts.positional_up_to(3)? {
+        [path] => certificate::width(&fs::read_to_string(path)?),
+        _ => opts.width(),
+    }
+}
+
+pub fn verify<const W: u32>(opts: &Options) -> Result<()>
+where
+    Width<W>: Storage,
+{
+    let collision = match opts.positional_up_to(3)? {
+        [path] => Collision::<W>::load(path)?,
+        _ => {
+            let positional = opts.positional(&["state", "message", "message"])?;
+            Collision {
+                h: opts.state::<W>(&positional[0])?,
+                m1: opts.state::<W>(&positional[1])?,
+                m2: opts.state::<W>(&positional[2])?,
+                params: opts.params()?,
+            }
+        }
+    };
+
+    collision.verify()?;
+    println!("OK");
+
+    Ok(())
+}
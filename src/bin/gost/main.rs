@@ -0,0 +1,118 @@
+//! # GOST tool
+//! This is a CLI interface for the library: the toy hash function, the toy Magma, the collision attack
+//! and the check of a found collision. Every command takes `--help`.
+//!
+//! `verify <file>` checks a collision certificate written by `attack --certificate <file>`.
+//!
+//! The states, the keys, the messages and the blocks are given as "bytes" of `W` bits
+//! (`--width`, 2 by default) separated by spaces, e.g. `"1 2 3 0 1 2 3 0 ..."`.
+//! `--sbox` chooses the S-box parameter set (`toy` by default).
+//! On failure the error is printed to stderr and the exit code tells what went wrong.
+//!
+//! Usage: `gost <command> [options] <args>`
+
+mod args;
+mod attack;
+mod commands;
+mod output;
+
+use args::Options;
+use attack::attack;
+use commands::{compress, decrypt, encrypt, hash, verify};
+use GOST_collision::budget::StopReason;
+use GOST_collision::error::{GostError, Result};
+use std::env;
+use std::process;
+
+static USAGE: &str = "Usage: gost <command> [options] <args>
+
+Commands:
+    hash        Hashes a message
+    compress    Applies the compression function to a state and a message block
+    encrypt     Encrypts a block with Magma
+    decrypt     Decrypts a block with Magma
+    attack      Finds a collision of the compression function
+    verify      Checks a collision
+
+Run `gost <command> --help` for the options of the command.";
+
+/// Calls the generic command with the given width
+macro_rules! with_width {
+    ($command:ident, $opts:expr, $width:expr) => {
+        match $width {
+            1 => $command::<1>($opts),
+            2 => $command::<2>($opts),
+            4 => $command::<4>($opts),
+            8 => $command::<8>($opts),
+            w => Err(GostError::Parse(format!("The width should be 1, 2, 4 or 8, got {}", w))),
+        }
+    };
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        process::exit(exit_code(&e));
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args = env::args().skip(1); // skipping the name of the program
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Err(GostError::Parse(format!("No command given\n\n{}", USAGE))),
+    };
+
+    let (help, with_value) = match command.as_str() {
+        "hash" => (&commands::HASH_HELP, commands::HASH_OPTIONS),
+        "compress" => (&commands::COMPRESS_HELP, commands::COMMON_OPTIONS),
+        "encrypt" | "decrypt" => (&commands::CIPHER_HELP, commands::COMMON_OPTIONS),
+        "attack" => (&attack::HELP, attack::OPTIONS),
+        "verify" => (&commands::VERIFY_HELP, commands::COMMON_OPTIONS),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        _ => return Err(GostError::Parse(format!("Unknown command {:?}\n\n{}", command, USAGE))),
+    };
+
+    let opts = Options::parse(args, with_value)?;
+    if opts.help {
+        help.print();
+        return Ok(());
+    }
+
+    let width = match command.as_str() {
+        "verify" => commands::verify_width(&opts)?,
+        "attack" => attack::attack_width(&opts)?,
+        _ => opts.width()?,
+    };
+
+    match command.as_str() {
+        "hash" => with_width!(hash, &opts, width),
+        "compress" => with_width!(compress, &opts, width),
+        "encrypt" => with_width!(encrypt, &opts, width),
+        "decrypt" => with_width!(decrypt, &opts, width),
+        "attack" => with_width!(attack, &opts, width),
+        _ => with_width!(verify, &opts, width),
+    }
+}
+
+fn exit_code(e: &GostError) -> i32 {
+    match e {
+        GostError::NotACollision | GostError::NotAFixedPoint => 1,
+        GostError::Parse(_) => 2,
+        GostError::WrongLength(_) => 3,
+        GostError::ByteOutOfRange { .. } => 4,
+        GostError::AsymmetricState => 5,
+        GostError::UnknownParamSet(_) | GostError::NonBijectiveSBox(_) => 6,
+        GostError::Io(_) => 7,
+        GostError::Checkpoint(_) => 8,
+        GostError::Certificate(_) => 9,
+        GostError::UnsupportedWidth(_) => 10,
+        GostError::OutOfMemory(_) => 11,
+        GostError::Poisoned => 70,
+        GostError::Stopped(StopReason::Cancelled) => 130,
+        GostError::Stopped(_) => 124,
+    }
+}
@@ -0,0 +1,117 @@
+//! The output of a found collision: text for people, hex and JSON for scripts
+
+use GOST_collision::certificate;
+use GOST_collision::error::{GostError, Result};
+use GOST_collision::format::Format;
+use GOST_collision::gost_collision::{utils, Collision};
+use GOST_collision::gost_hash::GostHash;
+use GOST_collision::magma::Block;
+use GOST_collision::width::{State, Storage, Width};
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Labeled "bytes" separated by spaces
+    Text,
+    /// Labeled packed words in hex
+    Hex,
+    Json,
+}
+
+impl FromStr for Output {
+    type Err = GostError;
+
+    fn from_str(s: &str) -> Result<Output> {
+        match s {
+            "text" => Ok(Output::Text),
+            "hex" => Ok(Output::Hex),
+            "json" => Ok(Output::Json),
+            _ => Err(GostError::Parse(format!("Unknown output {:?}, expected text, hex or json", s))),
+        }
+    }
+}
+
+/// The collision along with the details of the attack, which found it
+pub struct Found<const W: u32>
+where
+    Width<W>: Storage,
+{
+    pub collision: Collision<W>,
+    pub d: Block,
+    pub seed: Option<u64>,
+    pub rounds: u64,
+    pub restarts: u64,
+    pub elapsed: Duration,
+}
+
+impl<const W: u32> Found<W>
+where
+    Width<W>: Storage,
+{
+    pub fn print(&self, output: Output) {
+        let Collision { h, m1, m2, params } = self.collision;
+        let format = match output {
+            Output::Text => Format::Decimal,
+            _ => Format::Hex,
+        };
+        let state = |x: State<W>| {
+            let mut bytes = [0u8; 32];
+            utils::unpack::<W, _>(&mut bytes, x);
+            format.format::<W>(&bytes)
+        };
+        let mut d = [0u8; 8];
+        utils::unpack::<W, _>(&mut d, self.d);
+        let d = Format::Hex.format::<W>(&d);
+        let out = GostHash::<W>::compress_with(&params, h, m1);
+        let keys = |m| GostHash::<W>::keys(h, m).iter().map(|k| state(*k)).collect::<Vec<_>>();
+
+        match output {
+            Output::Json => {
+                let strings = |v: Vec<String>| v.iter().map(|s| format!("\"{}\"", s)).collect::<Vec<_>>().join(", ");
+                println!("{{");
+                println!("  \"width\": {},", W);
+                println!("  \"sbox\": \"{}\",", certificate::format_sbox(&params));
+                println!("  \"h\": \"{}\",", state(h));
+                println!("  \"m1\": \"{}\",", state(m1));
+                println!("  \"m2\": \"{}\",", state(m2));
+                println!("  \"output\": \"{}\",", state(out));
+                println!("  \"d\": \"{}\",", d);
+                // A string, as the numbers of JSON lose the precision above 2^53
+                println!(
+                    "  \"seed\": {},",
+                    self.seed.map_or("null".to_string(), |s| format!("\"{}\"", s))
+                );
+                println!("  \"rounds\": {},", self.rounds);
+                println!("  \"restarts\": {},", self.restarts);
+                println!("  \"elapsed\": {:.3},", self.elapsed.as_secs_f64());
+                println!("  \"keys\": {{");
+                println!("    \"m1\": [{}],", strings(keys(m1)));
+                println!("    \"m2\": [{}]", strings(keys(m2)));
+                println!("  }}");
+                println!("}}");
+            }
+            _ => {
+                println!("Width:    {}", W);
+                println!("S-boxes:  {}", certificate::format_sbox(&params));
+                println!("h:        {}", state(h));
+                println!("M1:       {}", state(m1));
+                println!("M2:       {}", state(m2));
+                println!("Output:   {}", state(out));
+                println!("d:        {}", d);
+                match self.seed {
+                    Some(seed) => println!("Seed:     {}", seed),
+                    None => println!("Seed:     unknown"),
+                }
+                println!("Rounds:   {}", self.rounds);
+                println!("Restarts: {}", self.restarts);
+                println!("Elapsed:  {:.3}s", self.elapsed.as_secs_f64());
+                for (name, m) in [("M1", m1), ("M2", m2)].iter() {
+                    for (i, k) in keys(*m).iter().enumerate() {
+                        println!("K{} of {}: {}", i + 1, name, k);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! # Bitsliced Magma
+//! Magma of `W` bit "bytes" for many (key, block) pairs at once. A batch of lanes is held
+//! in a word `L` per bit, `u64` for 64 lanes and `u128` for 128: the bit `i` of the word `j`
+//! is the bit `j` of the lane `i`. Then the S-boxes are multiplexer trees over the words,
+//! the key addition is a ripple-carry adder and the rotation only renames the words, so
+//! a round costs the same for a single lane and for all of them.
+//!
+//! `encrypt_rounds` and `decrypt_rounds` take a key per lane and run a range of rounds, as
+//! `Magma::encrypt_rounds` and `Magma::decrypt_rounds` do, so the seekers can run their 4 rounds
+//! on a batch of half-keys. For `W <= 2` a round of `Magma` is a single lookup in `RoundTable`,
+//! which beats a bitsliced round along with the packing of the lanes, so the seekers keep the tables.
+//! `cargo bench --bench magma` compares them on the inputs of the seekers.
+use crate::magma::{utils, Block, HalfBlock, ParamSet, RoundKey, ROUNDS};
+use crate::width::{State, Storage, Width, Word};
+use std::ops::Range;
+
+/// Up to `L::BITS` half-blocks or round keys, a word per bit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lanes<const W: u32, L: Word> {
+    bits: [L; 32],
+}
+
+/// The S-boxes of a parameter set, to be applied to the bitsliced half-blocks
+pub struct BitslicedMagma<const W: u32> {
+    /// The size of the chunks, which go through the S-boxes
+    chunk_bits: u32,
+    /// The S-box of every chunk, the chunk `i` takes the row `i`
+    rows: Vec<[u8; 16]>,
+    rounds: usize,
+}
+
+impl<const W: u32, L: Word> Lanes<W, L> {
+    /// Every lane holds `x`
+    pub fn splat(x: HalfBlock) -> Lanes<W, L> {
+        let mut bits = [L::default(); 32];
+        for (j, bit) in bits.iter_mut().enumerate().take(Width::<W>::HALF_BLOCK_BITS as usize) {
+            if (x >> j) & 0b1 == 1 {
+                *bit = !L::default();
+            }
+        }
+
+        Lanes { bits }
+    }
+
+    /// The lane `i` holds `words[i]`
+    /// # Panics
+    /// Panics if there are more words than lanes.
+    pub fn pack<I: IntoIterator<Item = HalfBlock>>(words: I) -> Lanes<W, L> {
+        let mut bits = [L::default(); 32];
+        for (i, x) in words.into_iter().enumerate() {
+            assert!(i < L::BITS as usize, "More words than lanes");
+            for (j, bit) in bits.iter_mut().enumerate().take(Width::<W>::HALF_BLOCK_BITS as usize) {
+                *bit |= L::from_u64((x >> j) as u64 & 0b1) << i as u32;
+            }
+        }
+
+        Lanes { bits }
+    }
+
+    /// Returns the half-block of the lane `i`
+    pub fn get(&self, i: usize) -> HalfBlock {
+        self.bits
+            .iter()
+            .take(Width::<W>::HALF_BLOCK_BITS as usize)
+            .enumerate()
+            .fold(0, |acc, (j, bit)| acc | (((*bit >> i as u32).low_u64() & 0b1) as HalfBlock) << j)
+    }
+
+    /// The lanes as a vector of half-blocks
+    pub fn unpack(&self, lanes: usize) -> Vec<HalfBlock> {
+        (0..lanes).map(|i| self.get(i)).collect()
+    }
+}
+
+impl<const W: u32> BitslicedMagma<W> {
+    pub fn new(params: &ParamSet) -> BitslicedMagma<W> {
+        Self::with_rounds(params, ROUNDS)
+    }
+
+    /// The cipher with the given number of rounds, see `Magma::with_rounds`
+    pub fn with_rounds(params: &ParamSet, rounds: usize) -> BitslicedMagma<W> {
+        let chunk_bits = utils::sbox_bits::<W>(params);
+        let mask = (1 << chunk_bits) - 1;
+        let rows = (0..Width::<W>::HALF_BLOCK_BITS / chunk_bits)
+            .map(|c| {
+                let shift = c * chunk_bits;
+                let mut row = [0; 16];
+                for (x, y) in row.iter_mut().enumerate().take(1 << chunk_bits) {
+                    *y = ((utils::s_box::<W>(params, (x as HalfBlock) << shift) >> shift) & mask) as u8;
+                }
+                row
+            })
+            .collect();
+
+        BitslicedMagma { chunk_bits, rows, rounds }
+    }
+
+    /// A round of Magma in every lane, see `Magma::round`
+    pub fn round<L: Word>(&self, left: &mut Lanes<W, L>, right: &mut Lanes<W, L>, key: &Lanes<W, L>) {
+        let bits = Width::<W>::HALF_BLOCK_BITS as usize;
+        let mut x = [L::default(); 32];
+        let mut carry = L::default();
+
+        // x = right + key
+        for (j, x) in x.iter_mut().enumerate().take(bits) {
+            let (a, b) = (right.bits[j], key.bits[j]);
+            *x = a ^ b ^ carry;
+            carry = (a & b) | (carry & (a ^ b));
+        }
+
+        let s = self.s_box(&x);
+        // The rotation takes the bit j to the bit j + ROTATION
+        for (j, s) in s.iter().enumerate().take(bits) {
+            left.bits[(j + Width::<W>::ROTATION as usize) % bits] ^= *s;
+        }
+
+        std::mem::swap(left, right);
+    }
+
+    /// Runs the rounds with the given keys in every lane
+    pub fn rounds<L: Word>(&self, left: &mut Lanes<W, L>, right: &mut Lanes<W, L>, keys: &[Lanes<W, L>]) {
+        for key in keys {
+            self.round(left, right, key);
+        }
+    }
+
+    /// Undoes `rounds` with the same keys
+    pub fn inverse_rounds<L: Word>(&self, left: &mut Lanes<W, L>, right: &mut Lanes<W, L>, keys: &[Lanes<W, L>]) {
+        // A round with swapped halves undoes itself
+        for key in keys.iter().rev() {
+            self.round(right, left, key);
+        }
+    }
+
+    /// Encrypts the block `blocks[i]` with the key `keys[i]`, see `Magma::encrypt_block`.
+    /// The blocks go in batches of `L::BITS`.
+    /// # Panics
+    /// Panics if the numbers of the keys and the blocks differ.
+    pub fn encrypt_blocks<L: Word>(&self, keys: &[State<W>], blocks: &[Block]) -> Vec<Block>
+    where
+        Width<W>: Storage,
+    {
+        let mut out = self.encrypt_rounds::<L>(keys, blocks, 0..self.rounds);
+        for block in out.iter_mut() {
+            *block = Self::swap_halves(*block);
+        }
+
+        out
+    }
+
+    /// Decrypts the block `blocks[i]` with the key `keys[i]`, see `Magma::decrypt_block`
+    /// # Panics
+    /// Panics if the numbers of the keys and the blocks differ.
+    pub fn decrypt_blocks<L: Word>(&self, keys: &[State<W>], blocks: &[Block]) -> Vec<Block>
+    where
+        Width<W>: Storage,
+    {
+        // This undoes the swap of the halves after the last round
+        let swapped: Vec<Block> = blocks.iter().map(|b| Self::swap_halves(*b)).collect();
+
+        self.decrypt_rounds::<L>(keys, &swapped, 0..self.rounds)
+    }
+
+    /// Runs the rounds of the range on the block `blocks[i]` with the key `keys[i]`,
+    /// see `Magma::encrypt_rounds` for the layout of the blocks
+    /// # Panics
+    /// Panics if the numbers of the keys and the blocks differ or the range goes beyond the rounds.
+    pub fn encrypt_rounds<L: Word>(&self, keys: &[State<W>], blocks: &[Block], rounds: Range<usize>) -> Vec<Block>
+    where
+        Width<W>: Storage,
+    {
+        self.batches(keys, blocks, rounds, |left, right, round_keys: &[Lanes<W, L>]| {
+            self.rounds(left, right, round_keys)
+        })
+    }
+
+    /// Undoes `encrypt_rounds` with the same keys and range, see `Magma::decrypt_rounds`
+    /// # Panics
+    /// Panics if the numbers of the keys and the blocks differ or the range goes beyond the rounds.
+    pub fn decrypt_rounds<L: Word>(&self, keys: &[State<W>], blocks: &[Block], rounds: Range<usize>) -> Vec<Block>
+    where
+        Width<W>: Storage,
+    {
+        self.batches(keys, blocks, rounds, |left, right, round_keys: &[Lanes<W, L>]| {
+            self.inverse_rounds(left, right, round_keys)
+        })
+    }
+
+    /// Packs the blocks and the round keys of the range in batches of `L::BITS` lanes,
+    /// runs `f` on every batch and unpacks the blocks
+    fn batches<L, F>(&self, keys: &[State<W>], blocks: &[Block], rounds: Range<usize>, f: F) -> Vec<Block>
+    where
+        Width<W>: Storage,
+        L: Word,
+        F: Fn(&mut Lanes<W, L>, &mut Lanes<W, L>, &[Lanes<W, L>]),
+    {
+        assert_eq!(keys.len(), blocks.len(), "A key per block is expected");
+        assert!(rounds.end <= self.rounds, "The cipher has {} rounds", self.rounds);
+        let half = |x: Block, i: u32| {
+            (x >> (i * Width::<W>::HALF_BLOCK_BITS)) as HalfBlock & Width::<W>::HALF_BLOCK_MASK
+        };
+        let subkey = |k: &State<W>, i: usize| {
+            (*k >> (i as u32 * Width::<W>::HALF_BLOCK_BITS)).low_u64() as RoundKey & Width::<W>::HALF_BLOCK_MASK
+        };
+        let mut out = Vec::with_capacity(blocks.len());
+
+        for (keys, blocks) in keys.chunks(L::BITS as usize).zip(blocks.chunks(L::BITS as usize)) {
+            // Only the subkeys of the range are packed
+            let mut subkeys: [Option<Lanes<W, L>>; 8] = [None; 8];
+            let round_keys: Vec<Lanes<W, L>> = rounds
+                .clone()
+                .map(|round| {
+                    let i = utils::subkey(self.rounds, round);
+                    *subkeys[i].get_or_insert_with(|| Lanes::pack(keys.iter().map(|k| subkey(k, i))))
+                })
+                .collect();
+            let mut left = Lanes::<W, L>::pack(blocks.iter().map(|b| half(*b, 0)));
+            let mut right = Lanes::<W, L>::pack(blocks.iter().map(|b| half(*b, 1)));
+
+            f(&mut left, &mut right, &round_keys);
+
+            out.extend((0..blocks.len()).map(|i| {
+                ((right.get(i) as Block) << Width::<W>::HALF_BLOCK_BITS) | left.get(i) as Block
+            }));
+        }
+
+        out
+    }
+
+    fn swap_halves(block: Block) -> Block {
+        let bits = Width::<W>::HALF_BLOCK_BITS;
+        let mask = Width::<W>::HALF_BLOCK_MASK as Block;
+
+        ((block & mask) << bits) | ((block >> bits) & mask)
+    }
+
+    /// Applies the S-boxes to the bitsliced `x`
+    fn s_box<L: Word>(&self, x: &[L; 32]) -> [L; 32] {
+        let n = self.chunk_bits as usize;
+        let mut s = [L::default(); 32];
+
+        for (c, row) in self.rows.iter().enumerate() {
+            let input = &x[c * n..(c + 1) * n];
+            for b in 0..n {
+                // The leaves are the bit b of the outputs, every level selects by the next input bit
+                let mut level = [L::default(); 16];
+                for (v, leaf) in level.iter_mut().enumerate().take(1 << n) {
+                    if (row[v] >> b) & 0b1 == 1 {
+                        *leaf = !L::default();
+                    }
+                }
+                for (k, select) in input.iter().enumerate() {
+                    for j in 0..1 << (n - k - 1) {
+                        let (lo, hi) = (level[2 * j], level[2 * j + 1]);
+                        level[j] = lo ^ (*select & (lo ^ hi));
+                    }
+                }
+                s[c * n + b] = level[0];
+            }
+        }
+
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitslicedMagma, Lanes};
+    use crate::magma::{Magma, ParamSet};
+    use crate::width::{State, Storage, Width, Word};
+    use std::ops::Range;
+
+    fn same_as_magma<const W: u32, L: Word>(params: ParamSet)
+    where
+        Width<W>: Storage,
+    {
+        let keys: Vec<State<W>> = (0..200u64)
+            .map(|i| State::<W>::from_limbs(&[i.wrapping_mul(0x9e37_79b9_7f4a_7c15), i, !i, i << 7]))
+            .collect();
+        let blocks: Vec<u64> = (0..200u64)
+            .map(|i| i.wrapping_mul(0xc2b2_ae3d_27d4_eb4f) & Width::<W>::BLOCK_MASK)
+            .collect();
+        let expected: Vec<u64> = keys
+            .iter()
+            .zip(blocks.iter())
+            .map(|(k, b)| Magma::<W>::with_params(*k, params).encrypt_block(*b))
+            .collect();
+
+        let bitsliced = BitslicedMagma::<W>::new(&params);
+
+        assert_eq!(
+            bitsliced.encrypt_blocks::<L>(&keys, &blocks),
+            expected,
+            "W = {}, {}",
+            W,
+            params.name()
+        );
+        assert_eq!(bitsliced.decrypt_blocks::<L>(&keys, &expected), blocks);
+    }
+
+    /// The ranges of the seekers and a reduced cipher, see `Magma::encrypt_rounds`
+    fn same_ranges_as_magma<const W: u32, L: Word>(rounds: usize, range: Range<usize>)
+    where
+        Width<W>: Storage,
+    {
+        let params = ParamSet::Gost2015;
+        let keys: Vec<State<W>> = (0..150u64)
+            .map(|i| State::<W>::from_limbs(&[i.wrapping_mul(0xff51_afd7_ed55_8ccd), !i, i, i << 3]))
+            .collect();
+        let blocks: Vec<u64> = (0..150u64).map(|i| (i * 0x0101_0101) & Width::<W>::BLOCK_MASK).collect();
+        let magmas: Vec<_> = keys.iter().map(|k| Magma::<W>::with_rounds(*k, params, rounds)).collect();
+        let bitsliced = BitslicedMagma::<W>::with_rounds(&params, rounds);
+
+        let encrypted = bitsliced.encrypt_rounds::<L>(&keys, &blocks, range.clone());
+        for ((magma, block), out) in magmas.iter().zip(blocks.iter()).zip(encrypted.iter()) {
+            assert_eq!(*out, magma.encrypt_rounds(*block, range.clone()), "W = {}, {:?}", W, range);
+        }
+        let decrypted = bitsliced.decrypt_rounds::<L>(&keys, &blocks, range.clone());
+        for ((magma, block), out) in magmas.iter().zip(blocks.iter()).zip(decrypted.iter()) {
+            assert_eq!(*out, magma.decrypt_rounds(*block, range.clone()), "W = {}, {:?}", W, range);
+        }
+        assert_eq!(bitsliced.decrypt_rounds::<L>(&keys, &encrypted, range), blocks);
+    }
+
+    #[test]
+    fn bitsliced_encryption() {
+        for params in ParamSet::NAMED.iter() {
+            same_as_magma::<1, u64>(*params);
+            same_as_magma::<2, u64>(*params);
+            same_as_magma::<2, u128>(*params);
+            same_as_magma::<4, u128>(*params);
+            same_as_magma::<8, u64>(*params);
+        }
+    }
+
+    #[test]
+    fn bitsliced_round_ranges() {
+        for range in [0..4, 4..8, 0..32, 20..30].iter() {
+            same_ranges_as_magma::<2, u128>(32, range.clone());
+            same_ranges_as_magma::<4, u64>(32, range.clone());
+            same_ranges_as_magma::<8, u128>(32, range.clone());
+        }
+        same_ranges_as_magma::<2, u64>(12, 0..12);
+        same_ranges_as_magma::<2, u64>(12, 2..9);
+    }
+
+    #[test]
+    fn lanes_roundtrip() {
+        let words: Vec<u32> = (0..100).map(|i| i * 37 % 256).collect();
+        let lanes = Lanes::<2, u128>::pack(words.iter().copied());
+
+        assert_eq!(lanes.unpack(100), words);
+        assert_eq!(Lanes::<2, u64>::splat(0xa5).unpack(64), vec![0xa5; 64]);
+    }
+}
@@ -0,0 +1,76 @@
+//! # Budget
+//! Limits of a long attack: a cancellation token, a wall-clock deadline and a cap on the number of
+//! fixed points kept in memory. The seekers check the token and the deadline while probing the keys,
+//! the cap is checked between the rounds.
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Why the attack stopped before finding a collision
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Cancelled,
+    Deadline,
+    /// The number of fixed points reached `Budget::max_fixed_points`
+    MemoryCap,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::Deadline => write!(f, "the deadline has passed"),
+            StopReason::MemoryCap => write!(f, "too many fixed points"),
+        }
+    }
+}
+
+/// A flag shared between the attack and whoever wants to stop it, e.g. a signal handler.
+/// The clones share the flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// The limits of the attack, there are none by default
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    pub cancel: CancelToken,
+    pub deadline: Option<Instant>,
+    pub max_fixed_points: Option<usize>,
+}
+
+impl Budget {
+    /// Returns the reason to stop the seekers, if the attack is cancelled or the deadline has passed
+    pub fn expired(&self) -> Option<StopReason> {
+        if self.cancel.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(StopReason::Deadline)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the reason to stop the attack, given the number of fixed points found so far
+    pub fn exhausted(&self, fixed_points: usize) -> Option<StopReason> {
+        self.expired().or_else(|| {
+            self.max_fixed_points
+                .filter(|max| fixed_points >= *max)
+                .map(|_| StopReason::MemoryCap)
+        })
+    }
+}
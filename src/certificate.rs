@@ -0,0 +1,200 @@
+//! # Certificate
+//! A text file with a collision, so it can be shared and checked independently of the attack:
+//!
+//! ```text
+//! # GOST collision certificate
+//! # output: 0x17f7c63d
+//! version: 1
+//! width: 1
+//! sbox: toy
+//! h: 0xac7134dd
+//! m1: 0x9287dc3f
+//! m2: 0x1d7e0932
+//! ```
+//!
+//! The words are packed as in `gost_collision::utils::pack`, see `format::Format::Hex`.
+//! A custom S-box is written as `sbox: custom` followed by 8 rows of 16 hex digits, `S(0)` first.
+//! The lines starting with `#` are comments, the output of the compression function is given there
+//! for the reader only.
+use crate::error::{GostError, Result};
+use crate::format::Format;
+use crate::gost_collision::{utils, Collision};
+use crate::gost_hash::GostHash;
+use crate::magma::{FullSBox, ParamSet};
+use crate::width::{State, Storage, Width};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub static VERSION: u32 = 1;
+
+/// Returns the width of the "bytes" of the certificate, so the caller can choose `W` to load it with
+pub fn width(text: &str) -> Result<u32> {
+    let fields = fields(text)?;
+    number(&fields, "width")
+}
+
+/// The name of the parameter set, a custom S-box is followed by its rows as in the certificate
+pub fn format_sbox(params: &ParamSet) -> String {
+    match params {
+        ParamSet::Custom(_) => params.sbox().iter().fold("custom".to_string(), |acc, row| {
+            acc + " " + &row.iter().map(|x| format!("{:x}", x)).collect::<String>()
+        }),
+        params => params.name().to_string(),
+    }
+}
+
+impl<const W: u32> Collision<W>
+where
+    Width<W>: Storage,
+{
+    pub fn to_certificate(&self) -> String {
+        let out = GostHash::<W>::compress_with(&self.params, self.h, self.m1);
+        format!(
+            "# GOST collision certificate\n# output: {}\nversion: {}\nwidth: {}\nsbox: {}\nh: {}\nm1: {}\nm2: {}\n",
+            Self::format_state(out),
+            VERSION,
+            W,
+            format_sbox(&self.params),
+            Self::format_state(self.h),
+            Self::format_state(self.m1),
+            Self::format_state(self.m2)
+        )
+    }
+
+    /// Reads the collision, the certificate should have been written with the same width.
+    /// The collision is not verified.
+    pub fn from_certificate(text: &str) -> Result<Collision<W>> {
+        let fields = fields(text)?;
+
+        let version: u32 = number(&fields, "version")?;
+        if version == 0 || version > VERSION {
+            return Err(GostError::Certificate(format!("Unsupported version {}", version)));
+        }
+        let width: u32 = number(&fields, "width")?;
+        if width != W {
+            return Err(GostError::Certificate(format!(
+                "The certificate is for {} bit bytes, not {}",
+                width, W
+            )));
+        }
+
+        Ok(Collision {
+            h: Self::parse_state(&fields, "h")?,
+            m1: Self::parse_state(&fields, "m1")?,
+            m2: Self::parse_state(&fields, "m2")?,
+            params: parse_params(field(&fields, "sbox")?)?,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.to_certificate())?;
+
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Collision<W>> {
+        Self::from_certificate(&fs::read_to_string(path)?)
+    }
+
+    fn format_state(x: State<W>) -> String {
+        let mut bytes = [0u8; 32];
+        utils::unpack::<W, _>(&mut bytes, x);
+
+        Format::Hex.format::<W>(&bytes)
+    }
+
+    fn parse_state(fields: &HashMap<&str, &str>, name: &str) -> Result<State<W>> {
+        let bytes = Format::Hex.parse::<W>(field(fields, name)?, Some(32))?;
+
+        Ok(utils::pack::<W, _>(&bytes))
+    }
+}
+
+/// Splits the lines into `name: value` pairs, skipping the comments
+fn fields(text: &str) -> Result<HashMap<&str, &str>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(':') {
+            Some((name, value)) => Ok((name.trim(), value.trim())),
+            None => Err(GostError::Certificate(format!("Expected `name: value`, got {:?}", line))),
+        })
+        .collect()
+}
+
+fn field<'a>(fields: &HashMap<&str, &'a str>, name: &str) -> Result<&'a str> {
+    fields
+        .get(name)
+        .copied()
+        .ok_or_else(|| GostError::Certificate(format!("No {} given", name)))
+}
+
+fn number<T: std::str::FromStr>(fields: &HashMap<&str, &str>, name: &str) -> Result<T> {
+    let value = field(fields, name)?;
+    value
+        .parse()
+        .map_err(|_| GostError::Certificate(format!("The {} should be a number, got {:?}", name, value)))
+}
+
+fn parse_params(value: &str) -> Result<ParamSet> {
+    let mut words = value.split_whitespace();
+    if words.next() != Some("custom") {
+        return value.parse();
+    }
+
+    let mut sbox: FullSBox = [[0; 16]; 8];
+    for row in sbox.iter_mut() {
+        let digits = words
+            .next()
+            .filter(|w| w.len() == 16)
+            .and_then(|w| w.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<Vec<u8>>>())
+            .ok_or_else(|| GostError::Certificate("A custom S-box should have 8 rows of 16 hex digits".to_string()))?;
+        row.copy_from_slice(&digits);
+    }
+
+    ParamSet::custom(sbox)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::GostError;
+    use crate::gost_collision::Collision;
+    use crate::magma::ParamSet;
+    use crate::width::{Word, U256};
+
+    #[test]
+    fn certificate_roundtrip() {
+        let mut sbox = *ParamSet::CryptoProC.sbox();
+        sbox.swap(1, 6);
+        let collision = Collision::<8> {
+            h: U256::from_limbs(&[5, 6, 7, 8]),
+            m1: U256::from_u64(1) << 255,
+            m2: U256::from_limbs(&[u64::MAX, 0, 1, 2]),
+            params: ParamSet::custom(sbox).unwrap(),
+        };
+
+        let text = collision.to_certificate();
+        assert_eq!(super::width(&text), Ok(8));
+        assert_eq!(Collision::<8>::from_certificate(&text), Ok(collision));
+        match Collision::<2>::from_certificate(&text) {
+            Err(GostError::Certificate(_)) => {}
+            _ => panic!("The width should be checked"),
+        }
+
+        let named = Collision::<2> {
+            h: 0x1b1b_1b1b_e4e4_e4e4,
+            m1: 1,
+            m2: 2,
+            params: ParamSet::CryptoProA,
+        };
+        assert!(named.to_certificate().contains("sbox: cryptopro-a\n"));
+        assert_eq!(Collision::<2>::from_certificate(&named.to_certificate()), Ok(named));
+
+        let broken = "version: 1\nwidth: 2\nsbox: toy\nh: 0x1\nm1: 0x2\n";
+        assert_eq!(
+            Collision::<2>::from_certificate(broken),
+            Err(GostError::Certificate("No m2 given".to_string()))
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! # Checkpoint
+//! On-disk snapshot of the fixed-point search, so a long attack can be resumed after the process is killed.
+//! The file is little endian:
+//!
+//! | field        | size                                                   |
+//! |--------------|--------------------------------------------------------|
+//! | magic        | 8 bytes, `GOSTCKPT`                                    |
+//! | version      | u32                                                    |
+//! | width `W`    | u32                                                    |
+//! | S-boxes      | u8, the index in `ParamSet::NAMED` or 255 and 128 bytes |
+//! | h            | `4 * W` bytes                                          |
+//! | d            | u64                                                    |
+//! | seed         | u8, 1 if it is known, and u64                          |
+//! | restarts     | u64, the number of d drawn before this one             |
+//! | round        | u64                                                    |
+//! | count        | u64                                                    |
+//! | fixed points | `count` pairs of the compression output and the message, `4 * W` bytes each |
+//! | collision    | u8, 1 if it is found, and two messages of `4 * W` bytes |
+use crate::error::{GostError, Result};
+use crate::magma::{Block, FullSBox, ParamSet};
+use crate::width::{State, Storage, Width, Word};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+static MAGIC: &[u8; 8] = b"GOSTCKPT";
+pub const VERSION: u32 = 1;
+static CUSTOM_SBOX: u8 = 255;
+
+/// The state of the fixed-point search
+pub struct Checkpoint<const W: u32>
+where
+    Width<W>: Storage,
+{
+    pub h: State<W>,
+    pub d: Block,
+    pub params: ParamSet,
+    /// The seed of the generator, which draws d
+    pub seed: Option<u64>,
+    /// The number of times the search has started over with a new d
+    pub restarts: u64,
+    /// The round to start from
+    pub round: u64,
+    /// Fixed points indexed by the output of the compression function
+    pub fixed_points: HashMap<State<W>, State<W>>,
+    /// The collision found in the last round, which the attack has not returned yet
+    pub collision: Option<(State<W>, State<W>)>,
+}
+
+/// Returns the width of the "bytes" of the checkpoint, so the caller can choose `W` to load it with
+pub fn width<P: AsRef<Path>>(path: P) -> Result<u32> {
+    read_header(&mut BufReader::new(File::open(path)?))
+}
+
+/// Checks the magic and the version, returns the width
+fn read_header<I: Read>(input: &mut I) -> Result<u32> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(GostError::Checkpoint("Not a checkpoint file".to_string()));
+    }
+    let version = read_u32(input)?;
+    if version != VERSION {
+        return Err(GostError::Checkpoint(format!("Unsupported version {}", version)));
+    }
+
+    read_u32(input)
+}
+
+fn read_u32<I: Read>(input: &mut I) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+impl<const W: u32> Checkpoint<W>
+where
+    Width<W>: Storage,
+{
+    /// Writes the checkpoint into a temporary file first, so the previous one survives a crash
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&W.to_le_bytes())?;
+        match ParamSet::NAMED.iter().position(|p| *p == self.params) {
+            Some(i) => out.write_all(&[i as u8])?,
+            None => {
+                out.write_all(&[CUSTOM_SBOX])?;
+                for row in self.params.sbox().iter() {
+                    out.write_all(row)?;
+                }
+            }
+        }
+        Self::write_word(&mut out, self.h)?;
+        out.write_all(&self.d.to_le_bytes())?;
+        out.write_all(&[self.seed.is_some() as u8])?;
+        out.write_all(&self.seed.unwrap_or_default().to_le_bytes())?;
+        out.write_all(&self.restarts.to_le_bytes())?;
+        out.write_all(&self.round.to_le_bytes())?;
+        out.write_all(&(self.fixed_points.len() as u64).to_le_bytes())?;
+        for (out_state, m) in self.fixed_points.iter() {
+            Self::write_word(&mut out, *out_state)?;
+            Self::write_word(&mut out, *m)?;
+        }
+        out.write_all(&[self.collision.is_some() as u8])?;
+        let (m1, m2) = self.collision.unwrap_or_default();
+        Self::write_word(&mut out, m1)?;
+        Self::write_word(&mut out, m2)?;
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Reads the checkpoint, which should have been written with the same width
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Checkpoint<W>> {
+        let mut input = BufReader::new(File::open(path)?);
+
+        let width = read_header(&mut input)?;
+        if width != W {
+            return Err(GostError::Checkpoint(format!(
+                "The checkpoint is for {} bit bytes, not {}",
+                width, W
+            )));
+        }
+
+        let mut tag = [0u8; 1];
+        input.read_exact(&mut tag)?;
+        let params = match ParamSet::NAMED.get(tag[0] as usize) {
+            Some(p) => *p,
+            None if tag[0] == CUSTOM_SBOX => {
+                let mut sbox: FullSBox = [[0; 16]; 8];
+                for row in sbox.iter_mut() {
+                    input.read_exact(row)?;
+                }
+                ParamSet::custom(sbox)?
+            }
+            None => return Err(GostError::Checkpoint(format!("Unknown S-box parameter set {}", tag[0]))),
+        };
+
+        let h = Self::read_word(&mut input)?;
+        let d = Self::read_u64(&mut input)?;
+        let mut known = [0u8; 1];
+        input.read_exact(&mut known)?;
+        let seed = Some(Self::read_u64(&mut input)?).filter(|_| known[0] == 1);
+        let restarts = Self::read_u64(&mut input)?;
+        let round = Self::read_u64(&mut input)?;
+        let count = Self::read_u64(&mut input)?;
+        let mut fixed_points = HashMap::new();
+        for _ in 0..count {
+            let out = Self::read_word(&mut input)?;
+            let m = Self::read_word(&mut input)?;
+            fixed_points.insert(out, m);
+        }
+        let mut found = [0u8; 1];
+        input.read_exact(&mut found)?;
+        let pair = (Self::read_word(&mut input)?, Self::read_word(&mut input)?);
+        let collision = Some(pair).filter(|_| found[0] == 1);
+
+        Ok(Checkpoint {
+            h,
+            d,
+            params,
+            seed,
+            restarts,
+            round,
+            fixed_points,
+            collision,
+        })
+    }
+
+    fn word_bytes() -> usize {
+        Width::<W>::STATE_BITS as usize / 8
+    }
+
+    fn write_word<O: Write>(out: &mut O, x: State<W>) -> Result<()> {
+        let bytes: Vec<u8> = x.to_limbs().iter().flat_map(|l| l.to_le_bytes().to_vec()).collect();
+        out.write_all(&bytes[..Self::word_bytes()])?;
+
+        Ok(())
+    }
+
+    fn read_word<I: Read>(input: &mut I) -> Result<State<W>> {
+        let mut bytes = [0u8; 32];
+        input.read_exact(&mut bytes[..Self::word_bytes()])?;
+        let limbs: Vec<u64> = bytes
+            .chunks(8)
+            .map(|c| u64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]))
+            .collect();
+
+        Ok(State::<W>::from_limbs(&limbs))
+    }
+
+    fn read_u64<I: Read>(input: &mut I) -> Result<u64> {
+        let mut bytes = [0u8; 8];
+        input.read_exact(&mut bytes)?;
+
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Checkpoint;
+    use crate::error::GostError;
+    use crate::magma::ParamSet;
+    use crate::width::{Word, U256};
+    use std::collections::HashMap;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.ckpt", name, std::process::id()))
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = temp_path("save_and_load");
+        let mut sbox = *ParamSet::CryptoProB.sbox();
+        sbox.swap(0, 7);
+        let checkpoint = Checkpoint::<8> {
+            h: U256::from_limbs(&[1, 2, 3, 4]),
+            d: 0xdead_beef,
+            params: ParamSet::custom(sbox).unwrap(),
+            seed: Some(7),
+            restarts: 3,
+            round: 42,
+            fixed_points: (0..100u64)
+                .map(|i| (U256::from_u64(i) << 200, U256::from_u64(!i)))
+                .collect::<HashMap<_, _>>(),
+            collision: Some((U256::from_u64(5) << 100, U256::from_u64(6))),
+        };
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::<8>::load(&path).unwrap();
+        assert_eq!(loaded.h, checkpoint.h);
+        assert_eq!(loaded.d, checkpoint.d);
+        assert_eq!(loaded.params, checkpoint.params);
+        assert_eq!(loaded.seed, checkpoint.seed);
+        assert_eq!(loaded.restarts, checkpoint.restarts);
+        assert_eq!(loaded.round, checkpoint.round);
+        assert_eq!(loaded.fixed_points, checkpoint.fixed_points);
+        assert_eq!(loaded.collision, checkpoint.collision);
+
+        assert_eq!(super::width(&path).unwrap(), 8);
+        match Checkpoint::<2>::load(&path) {
+            Err(GostError::Checkpoint(_)) => {}
+            _ => panic!("The width should be checked"),
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
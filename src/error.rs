@@ -0,0 +1,87 @@
+//! # Errors
+//! The errors of the library. The attack validates its input and reports the failures of the seekers
+//! instead of panicking.
+use crate::budget::StopReason;
+use std::fmt;
+use std::io;
+use std::sync::PoisonError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GostError {
+    /// The state or the message is not 32 "bytes" long, holds the actual length
+    WrongLength(usize),
+    /// The "byte" at `index` does not fit into the width
+    ByteOutOfRange { index: usize, value: u8, max: u8 },
+    /// The first quarter of the state is not symmetric, i.e. `h[0..4] != h[4..8]`
+    AsymmetricState,
+    /// The row of a user-supplied S-box is not a permutation of 0..16
+    NonBijectiveSBox(usize),
+    UnknownParamSet(String),
+    /// The input could not be parsed
+    Parse(String),
+    /// A seeker thread panicked, possibly leaving a lock poisoned
+    Poisoned,
+    /// Reading or writing a file failed
+    Io(String),
+    /// The checkpoint file is corrupted or does not match the attack
+    Checkpoint(String),
+    /// The messages do not collide
+    NotACollision,
+    /// The first instance of Magma does not keep the first block of the state for one of the messages
+    NotAFixedPoint,
+    /// The collision certificate is malformed or does not match the width
+    Certificate(String),
+    /// The keys of a round do not fit into memory, holds the number of the entries to allocate
+    OutOfMemory(u64),
+    /// The attack cannot enumerate the keys at this width, holds the width
+    UnsupportedWidth(u32),
+    /// The attack ran out of its budget before finding a collision,
+    /// the progress is saved into the checkpoint if there is one
+    Stopped(StopReason),
+}
+
+pub type Result<T> = std::result::Result<T, GostError>;
+
+impl fmt::Display for GostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GostError::WrongLength(len) => write!(f, "The state should be of size 32, got {}", len),
+            GostError::ByteOutOfRange { index, value, max } => write!(
+                f,
+                "The elements of the state should be from 0 to {}, got {} at position {}",
+                max, value, index
+            ),
+            GostError::AsymmetricState => write!(f, "The first quater of the state is not symmetric, the attack won't work"),
+            GostError::NonBijectiveSBox(row) => write!(f, "The row {} of the S-box is not a permutation", row),
+            GostError::UnknownParamSet(name) => write!(f, "Unknown S-box parameter set {:?}", name),
+            GostError::Parse(msg) => write!(f, "Cannot parse the input: {}", msg),
+            GostError::Poisoned => write!(f, "A seeker thread panicked"),
+            GostError::Io(msg) => write!(f, "I/O error: {}", msg),
+            GostError::Checkpoint(msg) => write!(f, "Bad checkpoint: {}", msg),
+            GostError::NotACollision => write!(f, "The messages do not collide"),
+            GostError::NotAFixedPoint => write!(f, "The messages are not fixed points of the first Magma"),
+            GostError::Certificate(msg) => write!(f, "Bad certificate: {}", msg),
+            GostError::OutOfMemory(n) => write!(f, "Cannot allocate {} entries for the keys of a round", n),
+            GostError::UnsupportedWidth(w) => write!(
+                f,
+                "The attack cannot enumerate the keys of {} bit bytes, there are 2^64 or more of them per round",
+                w
+            ),
+            GostError::Stopped(reason) => write!(f, "Stopped before finding a collision: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GostError {}
+
+impl From<io::Error> for GostError {
+    fn from(e: io::Error) -> GostError {
+        GostError::Io(e.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for GostError {
+    fn from(_: PoisonError<T>) -> GostError {
+        GostError::Poisoned
+    }
+}
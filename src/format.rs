@@ -0,0 +1,239 @@
+//! # Formats
+//! Text formats of the states, the messages and the blocks, which are sequences of `W` bit "bytes":
+//!
+//! * `decimal`: the "bytes" separated by spaces, the first one goes first, e.g. `1 2 3 0 ...`
+//! * `hex`: the packed word as a hexadecimal number, e.g. `0x1b1b1b1be4e4e4e4`
+//! * `binary`: the packed word as a binary number, e.g. `0b1101...`
+//! * `digits`: one digit of base `2^W` per "byte", the first one goes first, e.g. 32 base-4 digits for `W = 2`
+//!
+//! The words are packed as in `gost_collision::utils::pack`: the first "byte" goes to the lowest bits,
+//! so it is the *last* digit of a hexadecimal or a binary number.
+use crate::error::{GostError, Result};
+use crate::width::Width;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Decimal,
+    Hex,
+    Binary,
+    Digits,
+}
+
+impl FromStr for Format {
+    type Err = GostError;
+
+    fn from_str(s: &str) -> Result<Format> {
+        match s {
+            "decimal" => Ok(Format::Decimal),
+            "hex" => Ok(Format::Hex),
+            "binary" => Ok(Format::Binary),
+            "digits" | "base4" => Ok(Format::Digits),
+            _ => Err(GostError::Parse(format!(
+                "Unknown format {:?}, expected decimal, hex, binary or digits",
+                s
+            ))),
+        }
+    }
+}
+
+impl Format {
+    /// Guesses the format of `s`, which should hold `len` "bytes", if the length is known.
+    /// Spaces mean `decimal`, the prefixes `0x` and `0b` mean `hex` and `binary`.
+    /// Otherwise the length decides, and `digits` wins, when several formats fit,
+    /// e.g. 32 binary digits for `W = 1`. A number without a prefix is taken for `hex` or `binary`
+    /// only if the length is known.
+    pub fn detect<const W: u32>(s: &str, len: Option<usize>) -> Format {
+        let s = s.trim();
+        let bits = len.map(|len| len * W as usize);
+
+        if s.contains(char::is_whitespace) {
+            Format::Decimal
+        } else if s.starts_with("0x") {
+            Format::Hex
+        } else if s.starts_with("0b") {
+            Format::Binary
+        } else if W <= 4 && len.is_none_or(|len| s.len() == len) && Self::digit_values(s, W).is_some() {
+            Format::Digits
+        } else if bits.is_some_and(|bits| s.len() == bits.div_ceil(4)) && Self::digit_values(s, 4).is_some() {
+            Format::Hex
+        } else if bits.is_some_and(|bits| s.len() == bits) && Self::digit_values(s, 1).is_some() {
+            Format::Binary
+        } else {
+            Format::Decimal
+        }
+    }
+
+    /// Parses the "bytes". `len` is the number of "bytes" of a hexadecimal or a binary number,
+    /// otherwise it is given by the number of digits. The length is not checked otherwise.
+    pub fn parse<const W: u32>(&self, s: &str, len: Option<usize>) -> Result<Vec<u8>> {
+        let s = s.trim();
+
+        let bytes = match self {
+            Format::Decimal => s
+                .split_whitespace()
+                .map(|x| {
+                    x.parse::<u8>()
+                        .map_err(|_| GostError::Parse(format!("Incorrect characters in blocks: {:?}", x)))
+                })
+                .collect::<Result<Vec<u8>>>()?,
+            Format::Hex => Self::parse_number::<W>(s.strip_prefix("0x").unwrap_or(s), 4, len)?,
+            Format::Binary => Self::parse_number::<W>(s.strip_prefix("0b").unwrap_or(s), 1, len)?,
+            Format::Digits => {
+                if W > 4 {
+                    return Err(GostError::Parse(format!("There are no digits for {} bit bytes", W)));
+                }
+                Self::digit_values(s, W)
+                    .ok_or_else(|| GostError::Parse(format!("Incorrect base {} digits: {:?}", 1 << W, s)))?
+            }
+        };
+
+        match bytes.iter().position(|b| *b > Width::<W>::BYTE_MASK) {
+            Some(index) => Err(GostError::ByteOutOfRange {
+                index,
+                value: bytes[index],
+                max: Width::<W>::BYTE_MASK,
+            }),
+            None => Ok(bytes),
+        }
+    }
+
+    /// Formats the "bytes", so that `parse` gives them back
+    pub fn format<const W: u32>(&self, bytes: &[u8]) -> String {
+        match self {
+            Format::Decimal => bytes.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(" "),
+            Format::Hex => format!("0x{}", Self::format_number::<W>(bytes, 4)),
+            Format::Binary => format!("0b{}", Self::format_number::<W>(bytes, 1)),
+            Format::Digits => bytes.iter().map(|b| Self::digit(*b)).collect(),
+        }
+    }
+
+    /// Returns the values of the digits, if all of them are below `2^bits`
+    fn digit_values(s: &str, bits: u32) -> Option<Vec<u8>> {
+        s.chars()
+            .map(|c| c.to_digit(16).filter(|d| *d < 1 << bits).map(|d| d as u8))
+            .collect()
+    }
+
+    fn digit(x: u8) -> char {
+        std::char::from_digit(x as u32, 16).unwrap_or('?')
+    }
+
+    /// Parses a number, which has `bits` bits per digit, into `len` "bytes"
+    fn parse_number<const W: u32>(s: &str, bits: u32, len: Option<usize>) -> Result<Vec<u8>> {
+        let digits = Self::digit_values(s, bits)
+            .filter(|d| !d.is_empty())
+            .ok_or_else(|| GostError::Parse(format!("Incorrect base {} number: {:?}", 1 << bits, s)))?;
+        let total = digits.len() * bits as usize;
+        let len = match len {
+            Some(len) => len,
+            None if total.is_multiple_of(W as usize) => total / W as usize,
+            None => {
+                return Err(GostError::Parse(format!(
+                    "{} bits do not make whole {} bit bytes",
+                    total, W
+                )))
+            }
+        };
+        let mut bytes = vec![0u8; len];
+
+        // The last digit holds the lowest bits
+        for (i, d) in digits.iter().rev().enumerate() {
+            for j in 0..bits {
+                if (d >> j) & 0b1 == 0 {
+                    continue;
+                }
+                let bit = i * bits as usize + j as usize;
+                match bytes.get_mut(bit / W as usize) {
+                    Some(byte) => *byte |= 1 << (bit % W as usize),
+                    None => {
+                        return Err(GostError::Parse(format!(
+                            "The number {:?} has more than {} bits",
+                            s,
+                            len * W as usize
+                        )))
+                    }
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Formats the packed "bytes" as a number with `bits` bits per digit, the leading zeros are kept
+    fn format_number<const W: u32>(bytes: &[u8], bits: u32) -> String {
+        let total = bytes.len() * W as usize;
+        let bit = |i: usize| (bytes[i / W as usize] >> (i % W as usize)) & 0b1;
+
+        (0..total.div_ceil(bits as usize))
+            .rev()
+            .map(|d| {
+                let value = (0..bits as usize)
+                    .filter(|j| d * bits as usize + j < total)
+                    .fold(0, |acc, j| acc | bit(d * bits as usize + j) << j);
+                Self::digit(value)
+            })
+            .collect()
+    }
+}
+
+/// Parses the "bytes" in the given format or guesses it, see `Format::detect`
+pub fn parse<const W: u32>(s: &str, format: Option<Format>, len: Option<usize>) -> Result<Vec<u8>> {
+    format.unwrap_or_else(|| Format::detect::<W>(s, len)).parse::<W>(s, len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Format;
+    use crate::gost_collision::utils;
+
+    #[test]
+    fn formats_roundtrip() {
+        let bytes: Vec<u8> = (0..32).map(|i| (i * 7 % 5 % 4) as u8).collect();
+        let formats = [Format::Decimal, Format::Hex, Format::Binary, Format::Digits];
+
+        for format in formats.iter() {
+            let s = format.format::<2>(&bytes);
+            assert_eq!(format.parse::<2>(&s, Some(32)).unwrap(), bytes, "{:?}", format);
+            assert_eq!(super::parse::<2>(&s, None, Some(32)).unwrap(), bytes, "{:?} is not detected", format);
+        }
+
+        // 8 bit bytes have no digits
+        for format in formats[..3].iter() {
+            let bytes: Vec<u8> = (0..32).map(|i| (i * 37) as u8).collect();
+            let s = format.format::<8>(&bytes);
+            assert_eq!(super::parse::<8>(&s, None, Some(32)).unwrap(), bytes, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn pack_order() {
+        let bytes: Vec<u8> = (0..32).map(|i| (i * 3 % 4) as u8).collect();
+        let packed = utils::pack::<2, u64>(&bytes);
+
+        assert_eq!(Format::Hex.format::<2>(&bytes), format!("0x{:016x}", packed));
+        assert_eq!(Format::Binary.format::<2>(&bytes), format!("0b{:064b}", packed));
+        assert_eq!(
+            Format::Hex.parse::<2>(&format!("{:x}", packed), Some(32)).unwrap(),
+            bytes,
+            "Leading zeros may be omitted"
+        );
+        assert_eq!(Format::Digits.format::<2>(&bytes[..4]), "0321");
+
+        let mut unpacked = [0u8; 32];
+        utils::unpack::<2, _>(&mut unpacked, 0x1b1b_1b1b_e4e4_e4e4u64);
+        assert_eq!(super::parse::<2>("0x1b1b1b1be4e4e4e4", None, Some(32)).unwrap(), unpacked);
+        assert_eq!(super::parse::<2>("1b1b1b1be4e4e4e4", None, Some(32)).unwrap(), unpacked);
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!(Format::Hex.parse::<2>("0x1ffff", Some(8)).is_err(), "Too many bits");
+        assert!(Format::Digits.parse::<2>("0124", None).is_err());
+        assert!(Format::Decimal.parse::<2>("1 2 4", None).is_err());
+        assert!(Format::Hex.parse::<8>("0xabc", None).is_err(), "Half of a byte");
+        assert_eq!(Format::detect::<1>("0101", Some(4)), Format::Digits);
+        assert_eq!(Format::detect::<2>("7", None), Format::Decimal);
+        assert_eq!(Format::detect::<2>("7", Some(2)), Format::Hex);
+    }
+}
@@ -0,0 +1,504 @@
+//! # GF(2) linear algebra
+//! Bit vectors and bit matrices over GF(2). Used to reason about the linear maps of the hash function
+//! (`p`, `a`, `psy` and their compositions) exactly, instead of only evaluating them.
+use std::ops::BitXorAssign;
+
+static LIMB_BITS: usize = 64;
+
+/// A vector over GF(2) of a fixed length. Bit `i` is the `i`-th coordinate.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitVector {
+    len: usize,
+    limbs: Vec<u64>,
+}
+
+/// A matrix over GF(2), stored by rows. The `j`-th column is the image of the `j`-th base vector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitMatrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<BitVector>,
+}
+
+/// The set of solutions of A * x = d, that is `particular + span(kernel)`
+#[derive(Clone, Debug)]
+pub struct AffineSpace {
+    particular: BitVector,
+    kernel: Vec<BitVector>,
+}
+
+/// Result of the Gauss-Jordan elimination: `transform * matrix == reduced`
+struct Elimination {
+    reduced: BitMatrix,
+    transform: BitMatrix,
+    pivots: Vec<usize>,
+}
+
+impl BitVector {
+    pub fn zero(len: usize) -> BitVector {
+        BitVector {
+            len,
+            limbs: vec![0; len.div_ceil(LIMB_BITS)],
+        }
+    }
+
+    /// Returns the `i`-th base vector
+    pub fn unit(len: usize, i: usize) -> BitVector {
+        let mut v = Self::zero(len);
+        v.set(i, true);
+
+        v
+    }
+
+    /// Takes the lower `len` bits of `x`
+    /// # Panics
+    /// Panics if `len` is greater than 64.
+    pub fn from_u64(len: usize, x: u64) -> BitVector {
+        assert!(len <= LIMB_BITS, "The vector does not fit into u64");
+        let mut v = Self::zero(len);
+        if len > 0 {
+            v.limbs[0] = x & Self::mask(len);
+        }
+
+        v
+    }
+
+    /// Takes the lower `len` bits of the little endian limbs
+    pub fn from_limbs(len: usize, limbs: &[u64]) -> BitVector {
+        let mut v = Self::zero(len);
+        for (dst, src) in v.limbs.iter_mut().zip(limbs.iter()) {
+            *dst = *src;
+        }
+        v.trim();
+
+        v
+    }
+
+    /// Returns the lower 64 coordinates packed into u64
+    pub fn to_u64(&self) -> u64 {
+        self.limbs.first().cloned().unwrap_or_default()
+    }
+
+    pub fn limbs(&self) -> &[u64] {
+        &self.limbs
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|l| *l == 0)
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "Index is out of bounds");
+        (self.limbs[i / LIMB_BITS] >> (i % LIMB_BITS)) & 0b1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, bit: bool) {
+        assert!(i < self.len, "Index is out of bounds");
+        let m = 1 << (i % LIMB_BITS);
+        if bit {
+            self.limbs[i / LIMB_BITS] |= m;
+        } else {
+            self.limbs[i / LIMB_BITS] &= !m;
+        }
+    }
+
+    /// Scalar product over GF(2)
+    pub fn dot(&self, other: &BitVector) -> bool {
+        assert_eq!(self.len, other.len, "Lengths of the vectors differ");
+        self.limbs
+            .iter()
+            .zip(other.limbs.iter())
+            .fold(0, |acc, (a, b)| acc ^ (a & b).count_ones())
+            & 0b1
+            == 1
+    }
+
+    /// Number of non zero coordinates
+    pub fn weight(&self) -> usize {
+        self.limbs.iter().map(|l| l.count_ones() as usize).sum()
+    }
+
+    fn mask(bits: usize) -> u64 {
+        if bits >= LIMB_BITS {
+            !0
+        } else {
+            (1 << bits) - 1
+        }
+    }
+
+    /// Clears the bits above `len` in the last limb
+    fn trim(&mut self) {
+        let rem = self.len % LIMB_BITS;
+        if rem != 0 {
+            if let Some(last) = self.limbs.last_mut() {
+                *last &= Self::mask(rem);
+            }
+        }
+    }
+}
+
+impl<'a> BitXorAssign<&'a BitVector> for BitVector {
+    fn bitxor_assign(&mut self, rhs: &'a BitVector) {
+        assert_eq!(self.len, rhs.len, "Lengths of the vectors differ");
+        for (a, b) in self.limbs.iter_mut().zip(rhs.limbs.iter()) {
+            *a ^= b;
+        }
+    }
+}
+
+impl BitMatrix {
+    pub fn zero(rows: usize, cols: usize) -> BitMatrix {
+        BitMatrix {
+            rows,
+            cols,
+            data: vec![BitVector::zero(cols); rows],
+        }
+    }
+
+    pub fn identity(n: usize) -> BitMatrix {
+        let mut m = Self::zero(n, n);
+        for i in 0..n {
+            m.set(i, i, true);
+        }
+
+        m
+    }
+
+    /// Builds the matrix of a linear map GF(2)^cols -> GF(2)^rows given as a function on u64.
+    /// The map is evaluated on the base vectors only, so it **must** be linear.
+    /// # Panics
+    /// Panics if either of the dimensions is greater than 64.
+    pub fn from_fn<F>(rows: usize, cols: usize, f: F) -> BitMatrix
+    where
+        F: Fn(u64) -> u64,
+    {
+        assert!(cols <= LIMB_BITS, "The map does not act on u64");
+        Self::from_vector_fn(rows, cols, |x| BitVector::from_u64(rows, f(x.to_u64())))
+    }
+
+    /// Builds the matrix of a linear map GF(2)^cols -> GF(2)^rows given as a function on bit vectors.
+    /// The map is evaluated on the base vectors only, so it **must** be linear.
+    pub fn from_vector_fn<F>(rows: usize, cols: usize, f: F) -> BitMatrix
+    where
+        F: Fn(&BitVector) -> BitVector,
+    {
+        let columns: Vec<BitVector> = (0..cols).map(|j| f(&BitVector::unit(cols, j))).collect();
+
+        Self::from_columns(rows, &columns)
+    }
+
+    /// # Panics
+    /// Panics if the length of any column is not `rows`.
+    pub fn from_columns(rows: usize, columns: &[BitVector]) -> BitMatrix {
+        let mut m = Self::zero(rows, columns.len());
+        for (j, col) in columns.iter().enumerate() {
+            assert_eq!(col.len(), rows, "Column has a wrong length");
+            for i in 0..rows {
+                if col.get(i) {
+                    m.set(i, j, true);
+                }
+            }
+        }
+
+        m
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.data[row].get(col)
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, bit: bool) {
+        self.data[row].set(col, bit)
+    }
+
+    pub fn row(&self, i: usize) -> &BitVector {
+        &self.data[i]
+    }
+
+    pub fn column(&self, j: usize) -> BitVector {
+        let mut v = BitVector::zero(self.rows);
+        for i in 0..self.rows {
+            v.set(i, self.get(i, j));
+        }
+
+        v
+    }
+
+    /// Returns the matrix made of the columns in the range, i.e. the restriction
+    /// of the map to the corresponding coordinates
+    pub fn columns(&self, range: std::ops::Range<usize>) -> BitMatrix {
+        let columns: Vec<BitVector> = range.map(|j| self.column(j)).collect();
+
+        Self::from_columns(self.rows, &columns)
+    }
+
+    pub fn transpose(&self) -> BitMatrix {
+        let columns: Vec<BitVector> = self.data.clone();
+
+        Self::from_columns(self.cols, &columns)
+    }
+
+    /// Computes A * x
+    pub fn apply(&self, x: &BitVector) -> BitVector {
+        assert_eq!(x.len(), self.cols, "Vector has a wrong length");
+        let mut y = BitVector::zero(self.rows);
+        for (i, row) in self.data.iter().enumerate() {
+            y.set(i, row.dot(x));
+        }
+
+        y
+    }
+
+    /// Computes A * x for the maps acting on u64
+    pub fn apply_u64(&self, x: u64) -> u64 {
+        self.apply(&BitVector::from_u64(self.cols, x)).to_u64()
+    }
+
+    /// Returns the matrix of the composition `self(other(x))`
+    /// # Panics
+    /// Panics if the dimensions do not agree.
+    pub fn compose(&self, other: &BitMatrix) -> BitMatrix {
+        assert_eq!(self.cols, other.rows, "Dimensions of the matrices do not agree");
+        let mut m = Self::zero(self.rows, other.cols);
+        for (dst, row) in m.data.iter_mut().zip(self.data.iter()) {
+            for k in 0..self.cols {
+                if row.get(k) {
+                    *dst ^= other.row(k);
+                }
+            }
+        }
+
+        m
+    }
+
+    pub fn rank(&self) -> usize {
+        self.eliminate().pivots.len()
+    }
+
+    /// Returns a basis of the kernel
+    pub fn kernel(&self) -> Vec<BitVector> {
+        let e = self.eliminate();
+        let mut basis = Vec::with_capacity(self.cols - e.pivots.len());
+        let mut next_pivot = e.pivots.iter().peekable();
+
+        for free in 0..self.cols {
+            if next_pivot.peek() == Some(&&free) {
+                next_pivot.next();
+                continue;
+            }
+
+            // x_free = 1, and every pivot variable compensates its row
+            let mut v = BitVector::unit(self.cols, free);
+            for (i, p) in e.pivots.iter().enumerate() {
+                if e.reduced.get(i, free) {
+                    v.set(*p, true);
+                }
+            }
+            basis.push(v);
+        }
+
+        basis
+    }
+
+    /// Returns the inverse matrix or `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<BitMatrix> {
+        if self.rows != self.cols {
+            return None;
+        }
+
+        let e = self.eliminate();
+        if e.pivots.len() == self.rows {
+            Some(e.transform)
+        } else {
+            None
+        }
+    }
+
+    /// Returns all the solutions of A * x = d or `None` if there are none
+    pub fn solve(&self, d: &BitVector) -> Option<AffineSpace> {
+        assert_eq!(d.len(), self.rows, "Vector has a wrong length");
+        let e = self.eliminate();
+        let reduced_d = e.transform.apply(d);
+
+        if (e.pivots.len()..self.rows).any(|i| reduced_d.get(i)) {
+            return None;
+        }
+
+        let mut particular = BitVector::zero(self.cols);
+        for (i, p) in e.pivots.iter().enumerate() {
+            particular.set(*p, reduced_d.get(i));
+        }
+
+        Some(AffineSpace {
+            particular,
+            kernel: self.kernel(),
+        })
+    }
+
+    /// Gauss-Jordan elimination, keeping track of the row operations
+    fn eliminate(&self) -> Elimination {
+        let mut reduced = self.clone();
+        let mut transform = Self::identity(self.rows);
+        let mut pivots = Vec::new();
+
+        for col in 0..self.cols {
+            let r = pivots.len();
+            if r == self.rows {
+                break;
+            }
+
+            let found = match (r..self.rows).find(|i| reduced.get(*i, col)) {
+                Some(i) => i,
+                None => continue,
+            };
+            reduced.data.swap(r, found);
+            transform.data.swap(r, found);
+
+            for i in 0..self.rows {
+                if i != r && reduced.get(i, col) {
+                    let (row, pivot_row) = Self::pair_mut(&mut reduced.data, i, r);
+                    *row ^= pivot_row;
+                    let (row, pivot_row) = Self::pair_mut(&mut transform.data, i, r);
+                    *row ^= pivot_row;
+                }
+            }
+            pivots.push(col);
+        }
+
+        Elimination {
+            reduced,
+            transform,
+            pivots,
+        }
+    }
+
+    fn pair_mut(data: &mut [BitVector], i: usize, j: usize) -> (&mut BitVector, &BitVector) {
+        if i < j {
+            let (lo, hi) = data.split_at_mut(j);
+            (&mut lo[i], &hi[0])
+        } else {
+            let (lo, hi) = data.split_at_mut(i);
+            (&mut hi[0], &lo[j])
+        }
+    }
+}
+
+impl AffineSpace {
+    pub fn particular(&self) -> &BitVector {
+        &self.particular
+    }
+
+    pub fn kernel(&self) -> &[BitVector] {
+        &self.kernel
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.kernel.len()
+    }
+
+    /// Number of points in the space
+    /// # Panics
+    /// Panics if the dimension is 64 or more.
+    pub fn len(&self) -> u64 {
+        assert!(self.dimension() < LIMB_BITS, "The space is too large");
+        1 << self.dimension()
+    }
+
+    /// Always false, an affine space has at least one point
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the `n`-th point, the bits of `n` select the kernel vectors to add
+    pub fn get(&self, n: u64) -> BitVector {
+        let mut x = self.particular.clone();
+        for (i, v) in self.kernel.iter().enumerate().take(LIMB_BITS) {
+            if (n >> i) & 0b1 == 1 {
+                x ^= v;
+            }
+        }
+
+        x
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = BitVector> + '_ {
+        (0..self.len()).map(move |n| self.get(n))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitMatrix, BitVector};
+    use rand::Rng;
+
+    #[test]
+    fn from_fn_matches_evaluation() {
+        let f = |x: u64| x.rotate_left(7) ^ (x >> 3);
+        let m = BitMatrix::from_fn(64, 64, f);
+        let mut rand_gen = rand::thread_rng();
+
+        for _ in 0..128 {
+            let x = rand_gen.gen();
+            assert_eq!(m.apply_u64(x), f(x), "Matrix does not match the map");
+        }
+    }
+
+    #[test]
+    fn composition_and_inverse() {
+        let f = |x: u64| x ^ x.rotate_left(5) ^ x.rotate_left(17);
+        let g = |x: u64| x.rotate_right(11);
+        let mf = BitMatrix::from_fn(64, 64, f);
+        let mg = BitMatrix::from_fn(64, 64, g);
+
+        assert_eq!(mf.compose(&mg), BitMatrix::from_fn(64, 64, |x| f(g(x))));
+
+        let inv = mf.inverse().expect("The map is invertible");
+        assert_eq!(inv.compose(&mf), BitMatrix::identity(64));
+        assert_eq!(mf.compose(&inv), BitMatrix::identity(64));
+    }
+
+    #[test]
+    fn rank_kernel_and_solve() {
+        // Projection onto the lower 40 bits, then folded into 24 bits
+        let f = |x: u64| (x & 0xffffff) ^ ((x >> 24) & 0xffff);
+        let m = BitMatrix::from_fn(24, 64, f);
+        let kernel = m.kernel();
+
+        assert_eq!(m.rank(), 24);
+        assert_eq!(kernel.len(), 40);
+        assert!(m.inverse().is_none());
+        for v in kernel.iter() {
+            assert!(m.apply(v).is_zero(), "Kernel vector is not mapped to zero");
+        }
+
+        let d = BitVector::from_u64(24, 0xabcdef);
+        let space = m.solve(&d).expect("The map is surjective");
+        assert_eq!(space.dimension(), 40);
+        assert_eq!(m.apply(space.particular()), d);
+        for n in [0u64, 1, 0xdead, (1 << 40) - 1].iter() {
+            assert_eq!(m.apply(&space.get(*n)), d);
+        }
+    }
+
+    #[test]
+    fn solve_inconsistent() {
+        let m = BitMatrix::from_fn(16, 16, |x| x & 0xff);
+        assert!(m.solve(&BitVector::from_u64(16, 0x100)).is_none());
+    }
+}
@@ -1,346 +1,1165 @@
 //! # GOST Collission
 //! Finds a pair of message blocks, which both result in the same value after applying the GOST hash compersstion function.
-//! This works with the constraint that one "byte" is 2 bits long.
-use crate::gost_hash;
-use crate::magma;
-use indicatif::{ProgressBar, ProgressStyle};
-use rand::Rng;
-use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+//! This works with the constraint that one "byte" is `W` bits long (2 by default).
+use crate::budget::{Budget, StopReason};
+use crate::checkpoint::Checkpoint;
+use crate::error::{GostError, Result};
+use crate::gf2;
+use crate::gost_hash::GostHash;
+use crate::magma::{self, Block, ParamSet};
+use crate::pool::WorkerPool;
+use crate::progress::{NoProgress, Phase, ProgressSink};
+use crate::width::{HalfKey, State, Storage, Width, Word};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
-type State = u64;
-type Key = State;
-type HalfKey = u32;
-type Block = u16;
-type Message = State;
+type Key<const W: u32> = State<W>;
+type Message<const W: u32> = State<W>;
 
-static SEEKERS: u32 = 8;
+/// The number of seekers, when the number of CPUs is unknown
+static DEFAULT_SEEKERS: usize = 8;
+/// The seekers report the probed keys in batches of this size
+static PROGRESS_BATCH: u64 = 1 << 12;
 
 pub mod utils {
-    use std::convert::TryInto;
+    use crate::error::{GostError, Result};
+    use crate::width::{Width, Word};
 
-    pub fn pack<T>(x: &[u8]) -> T
-    where
-        T: From<u8> + std::ops::BitOrAssign + Default,
-    {
-        let mut tmp: T = Default::default();
+    /// Packs `W` bit "bytes" into a word, checking that every one of them fits into `W` bits
+    pub fn try_pack<const W: u32, T: Word>(x: &[u8]) -> Result<T> {
+        match x.iter().position(|e| *e > Width::<W>::BYTE_MASK) {
+            Some(index) => Err(GostError::ByteOutOfRange {
+                index,
+                value: x[index],
+                max: Width::<W>::BYTE_MASK,
+            }),
+            None => Ok(pack::<W, T>(x)),
+        }
+    }
+
+    /// Packs `W` bit "bytes" into a word, the first one goes to the lowest bits.
+    /// The bits above `W` are dropped.
+    pub fn pack<const W: u32, T: Word>(x: &[u8]) -> T {
+        let mut tmp = T::default();
         let mut offset = 0;
 
         for e in x.iter() {
-            tmp |= (*e << offset)
-                .try_into()
-                .expect("Cannot pack into this type.");
-            offset += 2;
+            tmp |= T::from_u64((*e & Width::<W>::BYTE_MASK) as u64) << offset;
+            offset += W;
         }
 
         tmp
     }
 
-    pub fn unpack<T>(x: &mut [u8], y: T)
-    where
-        T: std::ops::Shr + Default + std::ops::BitAnd + std::ops::AddAssign + TryInto<u64>,
-    {
-        let mut offset: u64 = Default::default();
-        if let Ok(val) = y.try_into() {
-            for elem in x.iter_mut() {
-                *elem = ((val >> offset) & 0b11u64) as u8;
-                offset += 2;
+    /// Sorts the pairs by the first `bits` bits of the key, 8 bits per pass.
+    /// The sort is stable, so the pairs with the same key keep their order.
+    pub fn radix_sort<T: Copy>(pairs: Vec<(u64, T)>, bits: u32) -> Vec<(u64, T)> {
+        let mut pairs = pairs;
+        let mut sorted = pairs.clone();
+
+        for shift in (0..bits).step_by(8) {
+            let digit = |key: u64| ((key >> shift) & 0xff) as usize;
+            let mut offsets = [0usize; 257];
+            for (key, _) in pairs.iter() {
+                offsets[digit(*key) + 1] += 1;
+            }
+            for d in 0..256 {
+                offsets[d + 1] += offsets[d];
+            }
+            for pair in pairs.iter() {
+                let d = digit(pair.0);
+                sorted[offsets[d]] = *pair;
+                offsets[d] += 1;
+            }
+            std::mem::swap(&mut pairs, &mut sorted);
+        }
+
+        pairs
+    }
+
+    pub fn unpack<const W: u32, T: Word>(x: &mut [u8], y: T) {
+        let mut offset = 0;
+
+        for elem in x.iter_mut() {
+            *elem = (y >> offset).low_u64() as u8 & Width::<W>::BYTE_MASK;
+            offset += W;
+        }
+    }
+}
+
+/// The affine space of solutions of A_i * k = d_i, that is `particular + span(kernel)`.
+/// This is `gf2::AffineSpace` packed into half-keys, so the seekers can enumerate it fast.
+struct Solutions<const W: u32>
+where
+    Width<W>: Storage,
+{
+    particular: HalfKey<W>,
+    kernel: Vec<HalfKey<W>>,
+}
+
+impl<const W: u32> From<gf2::AffineSpace> for Solutions<W>
+where
+    Width<W>: Storage,
+{
+    fn from(space: gf2::AffineSpace) -> Solutions<W> {
+        Solutions {
+            particular: HalfKey::<W>::from_limbs(space.particular().limbs()),
+            kernel: space.kernel().iter().map(|v| HalfKey::<W>::from_limbs(v.limbs())).collect(),
+        }
+    }
+}
+
+impl<const W: u32> Solutions<W>
+where
+    Width<W>: Storage,
+{
+    /// # Panics
+    /// Panics if the kernel has 64 or more dimensions, `GostAttack` does not take such widths.
+    fn len(&self) -> u64 {
+        assert!(self.kernel.len() < 64, "Too many solutions to enumerate");
+        1 << self.kernel.len()
+    }
+
+    /// Returns the `n`-th solution, the bits of `n` select the kernel vectors to add
+    fn get(&self, n: u64) -> HalfKey<W> {
+        let mut k = self.particular;
+
+        for (i, v) in self.kernel.iter().enumerate() {
+            if (n >> i) & 0b1 == 1 {
+                k ^= *v;
+            }
+        }
+
+        k
+    }
+
+    /// Enumerates the solutions of the range in the Gray code order: the `n`-th one is `get(n ^ (n >> 1))`,
+    /// so every next solution takes a single XOR of a kernel vector
+    fn gray(&self, range: Range<u64>) -> impl Iterator<Item = HalfKey<W>> + '_ {
+        let mut k = self.get(range.start ^ (range.start >> 1));
+
+        range.map(move |n| {
+            let current = k;
+            // The lowest set bit of n + 1 is the one to flip, there is none after the last solution
+            if let Some(v) = self.kernel.get((n + 1).trailing_zeros() as usize) {
+                k ^= *v;
             }
+
+            current
+        })
+    }
+}
+
+/// Two messages, which give the same output of the compression function for the state `h`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collision<const W: u32>
+where
+    Width<W>: Storage,
+{
+    pub h: State<W>,
+    pub m1: Message<W>,
+    pub m2: Message<W>,
+    pub params: ParamSet,
+}
+
+impl<const W: u32> Collision<W>
+where
+    Width<W>: Storage,
+{
+    /// Checks that the messages differ, give the same output of the compression function,
+    /// and the first instance of Magma keeps the first block of `h` for both of them,
+    /// i.e. the messages are the fixed points, which the attack searches for
+    pub fn verify(&self) -> Result<()> {
+        let out = GostHash::<W>::compress_with(&self.params, self.h, self.m1);
+        if self.m1 == self.m2 || out != GostHash::<W>::compress_with(&self.params, self.h, self.m2) {
+            return Err(GostError::NotACollision);
+        }
+
+        let block = self.h.low_u64() & Width::<W>::BLOCK_MASK;
+        for m in [self.m1, self.m2].iter() {
+            let k = GostHash::<W>::keys(self.h, *m)[0];
+            if magma::Magma::<W>::with_params(k, self.params).encrypt_block(block) != block {
+                return Err(GostError::NotAFixedPoint);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the messages as `W` bit "bytes"
+    pub fn messages(&self) -> ([u8; 32], [u8; 32]) {
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        utils::unpack::<W, _>(&mut first, self.m1);
+        utils::unpack::<W, _>(&mut second, self.m2);
+
+        (first, second)
+    }
+}
+
+/// The result of `generate_collision`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome<const W: u32>
+where
+    Width<W>: Storage,
+{
+    Collision(Collision<W>),
+    /// The budget ran out first
+    Partial(Partial<W>),
+}
+
+/// The progress of the attack, which ran out of its budget
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Partial<const W: u32>
+where
+    Width<W>: Storage,
+{
+    /// Fixed points found with the current d, indexed by the output of the compression function
+    pub fixed_points: HashMap<State<W>, Message<W>>,
+    /// Rounds completed with the current d
+    pub rounds: u64,
+    /// How many times the search has started over with a new d
+    pub restarts: u64,
+    pub reason: StopReason,
+}
+
+impl<const W: u32> Outcome<W>
+where
+    Width<W>: Storage,
+{
+    /// Returns the collision or `GostError::Stopped`
+    pub fn into_collision(self) -> Result<Collision<W>> {
+        match self {
+            Outcome::Collision(c) => Ok(c),
+            Outcome::Partial(p) => Err(GostError::Stopped(p.reason)),
         }
     }
 }
 
-struct GostAttackContext {
-    h: Arc<State>,
+struct GostAttackContext<const W: u32>
+where
+    Width<W>: Storage,
+{
+    h: Arc<State<W>>,
     d: Box<Block>,
-    fixed_points: Arc<RwLock<HashSet<Message>>>,
+    params: ParamSet,
+    /// The next round of the fixed-point search, gives d_1
+    round: usize,
+    /// Fixed points indexed by the output of the compression function
+    fixed_points: HashMap<State<W>, Message<W>>,
+    collision: Option<(Message<W>, Message<W>)>,
+}
+
+pub struct GostAttack<const W: u32 = 2>
+where
+    Width<W>: Storage,
+{
+    ctx: Box<GostAttackContext<W>>,
+    /// A_1, acts on sk0..sk3
+    first_operator: gf2::BitMatrix,
+    /// A_2, acts on sk4..sk7
+    second_operator: gf2::BitMatrix,
+    checkpoint: Option<CheckpointConfig>,
+    budget: Budget,
+    /// Draws d
+    rng: Box<dyn RngCore + Send>,
+    /// The seed of `rng`, if it is known
+    seed: Option<u64>,
+    /// How many times the search has started over with a new d
+    restarts: u64,
+    progress: Arc<dyn ProgressSink>,
+    /// Runs the seekers
+    pool: WorkerPool,
 }
 
-pub struct GostAttack {
-    ctx: Box<GostAttackContext>,
-    operator_on_base_vectors: Arc<[Block; 64]>,
+struct CheckpointConfig {
+    path: PathBuf,
+    interval: Duration,
+    last_saved: Instant,
 }
 
-impl GostAttack {
-    pub fn new(h: &[u8]) -> GostAttack {
-        let h_state = utils::pack(h);
+impl<const W: u32> GostAttack<W>
+where
+    Width<W>: Storage,
+{
+    /// # Panics
+    /// Panics if the state or the width is not valid, see `try_new`.
+    pub fn new(h: &[u8]) -> GostAttack<W> {
+        Self::with_params(h, ParamSet::Toy)
+    }
+
+    /// # Panics
+    /// Panics if the state or the width is not valid, see `try_new`.
+    pub fn with_params(h: &[u8], params: ParamSet) -> GostAttack<W> {
+        Self::try_with_params(h, params).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns the attack on the given state. The state should be 32 "bytes" of `W` bits
+    /// with the symmetric first quarter. Fails with `GostError::UnsupportedWidth` for 8 bit bytes,
+    /// as the keys of a round cannot be enumerated.
+    pub fn try_new(h: &[u8]) -> Result<GostAttack<W>> {
+        Self::try_with_params(h, ParamSet::Toy)
+    }
+
+    /// Returns the attack on the hash function, which uses the S-boxes of the given parameter set.
+    /// The seed is random, see `seed`.
+    pub fn try_with_params(h: &[u8], params: ParamSet) -> Result<GostAttack<W>> {
+        Self::try_with_seed(h, params, rand::thread_rng().gen())
+    }
+
+    /// Returns the attack, which draws d from `StdRng` with the given seed.
+    /// The same seed, state and parameters give the same collision.
+    pub fn try_with_seed(h: &[u8], params: ParamSet, seed: u64) -> Result<GostAttack<W>> {
+        let mut res = Self::try_with_rng(h, params, StdRng::seed_from_u64(seed))?;
+        res.seed = Some(seed);
+
+        Ok(res)
+    }
+
+    /// Returns the attack, which draws d from the given generator
+    pub fn try_with_rng<R>(h: &[u8], params: ParamSet, rng: R) -> Result<GostAttack<W>>
+    where
+        R: RngCore + Send + 'static,
+    {
+        let h_state = Self::check_state(h)?;
+        let half_key_bits = Width::<W>::STATE_BITS as usize / 2;
+        let first_operator = Self::get_operator_matrix().columns(0..half_key_bits);
+        let second_operator = Self::get_operator_matrix().columns(half_key_bits..2 * half_key_bits);
+        // The solutions of a side are counted in u64, see `Solutions::len`
+        if [&first_operator, &second_operator].iter().any(|a| a.cols() - a.rank() >= 64) {
+            return Err(GostError::UnsupportedWidth(W));
+        }
 
         let mut res = GostAttack {
             ctx: Box::new(GostAttackContext {
                 h: Arc::new(h_state),
-                d: Box::new(Default::default()),
-                fixed_points: Arc::new(RwLock::new(HashSet::new())),
+                d: Box::default(),
+                params,
+                round: 0,
+                fixed_points: HashMap::new(),
+                collision: None,
             }),
-            operator_on_base_vectors: Arc::new(Self::get_operator_values()),
+            first_operator,
+            second_operator,
+            checkpoint: None,
+            budget: Budget::default(),
+            rng: Box::new(rng),
+            seed: None,
+            restarts: 0,
+            progress: Arc::new(NoProgress),
+            pool: WorkerPool::new(thread::available_parallelism().map_or(DEFAULT_SEEKERS, |n| n.get())),
         };
 
         res.calculate_d();
 
-        res
+        Ok(res)
     }
 
-    pub fn generate_collision(&mut self) -> ([u8; 32], [u8; 32]) {
-        loop {
-            self.find_fixed_points();
-            if let Some(collision) = self.get_collision() {
-                let mut first = [0u8; 32];
-                let mut second = [0u8; 32];
-                utils::unpack(&mut first, collision.0);
-                utils::unpack(&mut second, collision.1);
-
-                return (first, second);
-            }
-            self.ctx.fixed_points.write().unwrap().clear();
+    /// Restores the attack from the checkpoint. The checkpoint is not written again unless
+    /// `set_checkpoint` is called.
+    pub fn resume<P: AsRef<Path>>(path: P) -> Result<GostAttack<W>> {
+        let checkpoint = Checkpoint::<W>::load(path)?;
+        let mut h = [0u8; 32];
+        utils::unpack::<W, _>(&mut h, checkpoint.h);
+
+        let mut res = match checkpoint.seed {
+            Some(seed) => Self::try_with_seed(&h, checkpoint.params, seed)?,
+            None => Self::try_with_params(&h, checkpoint.params)?,
+        };
+        res.seed = checkpoint.seed;
+        // Skip the values of d, which have been used already
+        for _ in 0..checkpoint.restarts {
+            res.rng.next_u64();
         }
+        res.restarts = checkpoint.restarts;
+        *res.ctx.d = checkpoint.d & Width::<W>::BLOCK_MASK;
+        res.ctx.round = checkpoint.round as usize;
+        res.ctx.fixed_points = checkpoint.fixed_points;
+        // The collision of the last round, `round` is past it already
+        res.ctx.collision = checkpoint.collision;
+
+        Ok(res)
     }
 
-    fn calculate_d(&mut self) {
-        let mut c = rand::thread_rng().gen::<Block>();
+    /// Saves the progress into the file every `interval`, when it is interrupted and before starting over
+    pub fn set_checkpoint<P: Into<PathBuf>>(&mut self, path: P, interval: Duration) {
+        self.checkpoint = Some(CheckpointConfig {
+            path: path.into(),
+            interval,
+            last_saved: Instant::now(),
+        });
+    }
 
-        let y = gost_hash::GostHash::psy_pow(*self.ctx.h, -12);
-        c ^= (y & 0xffff) as Block; // c xor psy^-12(h), this comes from the z0
-        let y = gost_hash::GostHash::psy_pow(y, -1);
-        c ^= (y & 0xffff) as Block; // d1 xor psy^-13(h), this is y0 actually
+    /// Limits the attack, `generate_collision` returns `Outcome::Partial` once the budget runs out.
+    /// The attack can go on with a new budget after that.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
 
-        self.ctx.d = Box::new(c);
+    /// Returns a snapshot of the fixed-point search
+    pub fn checkpoint(&self) -> Checkpoint<W> {
+        Checkpoint {
+            h: *self.ctx.h,
+            d: *self.ctx.d,
+            params: self.ctx.params,
+            seed: self.seed,
+            restarts: self.restarts,
+            round: self.ctx.round as u64,
+            fixed_points: self.ctx.fixed_points.clone(),
+            collision: self.ctx.collision,
+        }
     }
 
-    fn find_fixed_points(&mut self) {
-        let mut i = 0;
-        let pb = ProgressBar::new(16777216);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("[{bar:50.green/green}] {pos}/{len} {msg}")
-                .progress_chars("#>-"),
-        );
-        pb.set_message("Fixed Points");
+    /// Runs the seekers on the given number of threads, one per CPU by default.
+    /// The collision does not depend on it.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.pool = WorkerPool::new(threads);
+    }
 
-        while self.ctx.fixed_points.read().unwrap().len() < 16777216 {
-            // (2^24)
-            pb.set_position(self.ctx.fixed_points.read().unwrap().len() as u64);
-            self.find_fixed_points_round(i);
-            i += 1;
+    /// Reports the progress into the sink, nothing is reported by default
+    pub fn set_progress(&mut self, progress: Arc<dyn ProgressSink>) {
+        self.progress = progress;
+    }
+
+    /// The seed of the generator, which draws d. `None`, if the generator was supplied by the caller.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The state under attack
+    pub fn h(&self) -> State<W> {
+        *self.ctx.h
+    }
+
+    /// The right-hand side of `A * k = d`, the keys of the fixed points solve it
+    pub fn d(&self) -> Block {
+        *self.ctx.d
+    }
+
+    /// The number of rounds of the fixed-point search completed with the current d
+    pub fn rounds(&self) -> u64 {
+        self.ctx.round as u64
+    }
+
+    /// How many times the search has started over with a new d
+    pub fn restarts(&self) -> u64 {
+        self.restarts
+    }
+
+    pub fn params(&self) -> ParamSet {
+        self.ctx.params
+    }
+
+    /// Writes the checkpoint, if it is configured
+    pub fn save_checkpoint(&mut self) -> Result<()> {
+        if let Some(config) = self.checkpoint.as_ref() {
+            self.checkpoint().save(&config.path)?;
+        }
+        if let Some(config) = self.checkpoint.as_mut() {
+            config.last_saved = Instant::now();
         }
 
-        pb.finish_and_clear();
+        Ok(())
     }
 
-    fn find_fixed_points_round(&mut self, i: usize) {
-        let d1 = i as Block;
-        let d2 = d1 ^ *self.ctx.d;
+    fn save_checkpoint_if_due(&mut self) -> Result<()> {
+        match self.checkpoint.as_ref() {
+            Some(config) if config.last_saved.elapsed() >= config.interval => self.save_checkpoint(),
+            _ => Ok(()),
+        }
+    }
 
-        let l = self.seek_forward(d1);
-        self.seek_backward(l, d2);
+    /// Checks that the state can be attacked and packs it
+    pub fn check_state(h: &[u8]) -> Result<State<W>> {
+        if h.len() != 32 {
+            return Err(GostError::WrongLength(h.len()));
+        }
+        let h_state = utils::try_pack::<W, _>(h)?;
+        if h[..4] != h[4..8] {
+            return Err(GostError::AsymmetricState);
+        }
+
+        Ok(h_state)
     }
 
-    fn seek_forward(&self, d1: Block) -> Arc<RwLock<HashMap<Block, HalfKey>>> {
-        let l = Arc::new(RwLock::new(HashMap::new()));
-        let mut seekers = Vec::with_capacity(SEEKERS as usize);
-        let pb = ProgressBar::new(std::u32::MAX as u64);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] [{bar:50.cyan/blue}] {pos}/{len} {msg}")
-                .progress_chars("#>-"),
-        );
-        pb.set_message("Keys probed");
-
-        for i in 0..SEEKERS {
-            let l_copy = l.clone();
-            let h = self.ctx.h.clone();
-            let pb = pb.clone();
-            let b = self.operator_on_base_vectors.clone();
-
-            // UGLY should write it into a separate function
-            seekers.push(thread::spawn(move || {
-                let step = std::u32::MAX / SEEKERS as u32;
-                let first = i * step;
-                let second = if i == SEEKERS - 1 {
-                    std::u32::MAX
-                } else {
-                    (i + 1) * step
-                };
-
-                let _left = (*h & 0xff) as u8;
-                let _right = ((*h >> 8) & 0xff) as u8;
-
-                for half_key in first..second {
-                    pb.inc(1);
-
-                    if Self::check_equasion(b.clone(), half_key, d1, true) {
-                        let mut left = _left;
-                        let mut right = _right;
-
-                        magma::Magma::round(&mut left, &mut right, (half_key & 0xff) as u8);
-                        magma::Magma::round(&mut left, &mut right, ((half_key >> 8) & 0xff) as u8);
-                        magma::Magma::round(&mut left, &mut right, ((half_key >> 16) & 0xff) as u8);
-                        magma::Magma::round(&mut left, &mut right, ((half_key >> 24) & 0xff) as u8);
-
-                        let block = ((right as u16) << 8) | left as u16;
-
-                        l_copy
-                            .write()
-                            .expect("Cannot acquire the lock")
-                            .insert(block, half_key);
-                    }
-                }
-            }));
+    /// Runs until a collision is found or the budget runs out.
+    /// Fails, if any of the seekers panics or the checkpoint cannot be written.
+    pub fn generate_collision(&mut self) -> Result<Outcome<W>> {
+        loop {
+            if let Some(reason) = self.find_fixed_points()? {
+                return Ok(Outcome::Partial(Partial {
+                    fixed_points: self.ctx.fixed_points.clone(),
+                    rounds: self.ctx.round as u64,
+                    restarts: self.restarts,
+                    reason,
+                }));
+            }
+            if let Some((m1, m2)) = self.ctx.collision {
+                self.progress.collision_found();
+
+                return Ok(Outcome::Collision(Collision {
+                    h: *self.ctx.h,
+                    m1,
+                    m2,
+                    params: self.ctx.params,
+                }));
+            }
+            self.ctx.fixed_points.clear();
+            // The same d would give the same fixed points again
+            self.calculate_d();
+            self.restarts += 1;
+            self.ctx.round = 0;
+            self.save_checkpoint()?;
         }
+    }
+
+    fn calculate_d(&mut self) {
+        let mut c = self.rng.next_u64() & Width::<W>::BLOCK_MASK;
+
+        let y = GostHash::<W>::psy_pow(*self.ctx.h, -12);
+        c ^= y.low_u64() & Width::<W>::BLOCK_MASK; // c xor psy^-12(h), this comes from the z0
+        let y = GostHash::<W>::psy_pow(y, -1);
+        c ^= y.low_u64() & Width::<W>::BLOCK_MASK; // d1 xor psy^-13(h), this is y0 actually
+
+        *self.ctx.d = c;
+    }
 
-        for hnd in seekers {
-            hnd.join().unwrap();
+    /// The number of fixed points to collect before starting over, 2^(12 * W).
+    /// Saturates, when it does not fit into u64.
+    fn fixed_points_target() -> u64 {
+        1u64.checked_shl(12 * W).unwrap_or(u64::MAX)
+    }
+
+    /// Collects the fixed points until there are enough of them or a collision is found.
+    /// Returns the reason, if the budget runs out first.
+    fn find_fixed_points(&mut self) -> Result<Option<StopReason>> {
+        let target = Self::fixed_points_target();
+        self.progress.phase_start(Phase::FixedPoints, target);
+
+        while (self.ctx.fixed_points.len() as u64) < target && self.ctx.collision.is_none() {
+            let found = self.ctx.fixed_points.len();
+            self.progress.fixed_points_found(found as u64);
+
+            let stopped = match self.budget.exhausted(found) {
+                Some(reason) => Some(reason),
+                None => self.find_fixed_points_round(self.ctx.round)?,
+            };
+            if let Some(reason) = stopped {
+                self.progress.phase_end(Phase::FixedPoints);
+                self.save_checkpoint()?;
+                return Ok(Some(reason));
+            }
+            self.ctx.round += 1;
+            self.save_checkpoint_if_due()?;
         }
 
-        pb.finish_and_clear();
+        self.progress.phase_end(Phase::FixedPoints);
 
-        l
+        Ok(None)
     }
 
-    fn seek_backward(&mut self, l: Arc<RwLock<HashMap<Block, HalfKey>>>, d2: Block) {
-        let mut seekers = Vec::with_capacity(SEEKERS as usize);
-        let pb = ProgressBar::new(std::u32::MAX as u64);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] [{bar:50.cyan/blue}] {pos}/{len} {msg}")
-                .progress_chars("#>-"),
+    /// Returns the reason, if the seekers were stopped. The fixed points of such a round are dropped,
+    /// so the round is run again from the start.
+    fn find_fixed_points_round(&mut self, i: usize) -> Result<Option<StopReason>> {
+        let d1 = i as Block & Width::<W>::BLOCK_MASK;
+        let d2 = d1 ^ *self.ctx.d;
+        let block_bits = Width::<W>::BLOCK_BITS as usize;
+
+        // A_1 * k = d_1 and A_2 * k = d_2 are independent, as A_1 acts on sk0..sk3 and A_2 on sk4..sk7
+        let forward = self.first_operator.solve(&gf2::BitVector::from_u64(block_bits, d1));
+        let backward = self.second_operator.solve(&gf2::BitVector::from_u64(block_bits, d2));
+        let (forward, backward): (Solutions<W>, Solutions<W>) = match (forward, backward) {
+            (Some(f), Some(b)) => (f.into(), b.into()),
+            _ => return Ok(None),
+        };
+        debug_assert_eq!(
+            Self::_apply_operator(Self::join_key(forward.particular, backward.particular)),
+            *self.ctx.d
         );
-        pb.set_message("Keys probed backwards");
-
-        for i in 0..SEEKERS {
-            let h = self.ctx.h.clone();
-            let fixed_points = self.ctx.fixed_points.clone();
-            let l_copy = l.clone();
-            let pb = pb.clone();
-            let b = self.operator_on_base_vectors.clone();
-
-            seekers.push(thread::spawn(move || {
-                let step = std::u32::MAX / SEEKERS as u32;
-                let first = i * step;
-                let second = if i == SEEKERS - 1 {
-                    std::u32::MAX
-                } else {
-                    (i + 1) * step
-                };
-
-                let _right = (*h & 0xff) as u8;
-                let _left = ((*h >> 8) & 0xff) as u8;
-
-                for half_key in first..second {
-                    pb.inc(1);
-
-                    if Self::check_equasion(b.clone(), half_key, d2, false) {
-                        let mut left = _left;
-                        let mut right = _right;
-
-                        // Those are reversed
-                        magma::Magma::round(&mut left, &mut right, ((half_key >> 24) & 0xff) as u8);
-                        magma::Magma::round(&mut left, &mut right, ((half_key >> 16) & 0xff) as u8);
-                        magma::Magma::round(&mut left, &mut right, ((half_key >> 8) & 0xff) as u8);
-                        magma::Magma::round(&mut left, &mut right, (half_key & 0xff) as u8);
-
-                        // In this case the upper bits are in the left part
-                        let block = ((left as u16) << 8) | right as u16;
-                        let read_lock = l_copy.read().expect("Cannot acquire read lock");
-                        if read_lock.contains_key(&block) {
-                            // sk3||sk2||sk1||sk0
-                            let first_key_half = read_lock.get(&block).unwrap();
-                            // sk7||...||sk0
-                            let key = ((half_key as Key) << 32) | *first_key_half as u64;
-
-                            fixed_points
-                                .write()
-                                .expect("Cannot acquire write lock")
-                                .insert(Self::convert_to_message(*h, key));
-                        }
-                    }
-                }
-            }));
+
+        let forward = self.seek_forward(Arc::new(forward))?;
+        if let Some(reason) = self.budget.expired() {
+            return Ok(Some(reason));
+        }
+        let backward = self.seek_backward(Arc::new(backward))?;
+        // The budget does not come back, so it has expired here, if any of the seekers has stopped
+        if let Some(reason) = self.budget.expired() {
+            return Ok(Some(reason));
         }
 
-        for hnd in seekers {
-            hnd.join().unwrap();
+        for (out, m) in self.join(forward, backward)? {
+            Self::add_fixed_point(&mut self.ctx.fixed_points, &mut self.ctx.collision, out, m);
         }
 
-        pb.finish_and_clear();
+        Ok(None)
     }
 
-    fn convert_to_message(h: State, key: Key) -> Message {
-        gost_hash::GostHash::p_rev(key) ^ h
+    /// Encrypts the first block of h with the rounds 1..4 (sk0..sk3) of every solution,
+    /// returns the blocks sorted
+    fn seek_forward(&self, solutions: Arc<Solutions<W>>) -> Result<Vec<(Block, HalfKey<W>)>> {
+        let block = self.ctx.h.low_u64() & Width::<W>::BLOCK_MASK;
+
+        self.seek(Phase::Forward, solutions, move |magma, half_key| {
+            // Only the round keys of these rounds are scheduled
+            magma.rekey_rounds(Self::join_key(half_key, HalfKey::<W>::default()), 0..4);
+            magma.encrypt_rounds(block, 0..4)
+        })
     }
 
-    /// Check, whether equasion A_i * k = d_i holds for a given k, d_i and i
-    /// if first_half is true, then equasion for A_1 is computed
-    /// else equasion for A_2 is computed
-    fn check_equasion(b: Arc<[Block; 64]>, half_key: HalfKey, d: Block, first_half: bool) -> bool {
-        let mut mock_key = half_key as Key; // Placed sk0 .. sk3
-        if !first_half {
-            mock_key <<= 32; // Placed sk4 .. sk7
-        }
+    /// Decrypts the first block of h with the rounds 5..8 (sk4..sk7) of every solution,
+    /// returns the blocks sorted
+    fn seek_backward(&self, solutions: Arc<Solutions<W>>) -> Result<Vec<(Block, HalfKey<W>)>> {
+        let block = self.ctx.h.low_u64() & Width::<W>::BLOCK_MASK;
+
+        self.seek(Phase::Backward, solutions, move |magma, half_key| {
+            magma.rekey_rounds(Self::join_key(HalfKey::<W>::default(), half_key), 4..8);
+            magma.decrypt_rounds(block, 4..8)
+        })
+    }
 
-        Self::apply_operator(b, mock_key) == d
+    /// Every seeker of the pool maps the solutions of its range to the blocks in a buffer of its own,
+    /// so they share no locks. The buffers are concatenated in the order of the ranges and sorted.
+    /// The sort is stable, so the result does not depend on the thread scheduling.
+    /// The result is incomplete, if the budget has expired.
+    /// Every seeker gets an instance of Magma of its own to rekey.
+    fn seek<F>(&self, phase: Phase, solutions: Arc<Solutions<W>>, block: F) -> Result<Vec<(Block, HalfKey<W>)>>
+    where
+        F: Fn(&mut magma::Magma<W>, HalfKey<W>) -> Block + Send + Sync + 'static,
+    {
+        let magma = magma::Magma::<W>::with_params(Key::<W>::default(), self.ctx.params);
+        let progress = self.progress.clone();
+        let budget = self.budget.clone();
+        let seekers = self.pool.threads();
+        // The blocks of the side along with the buffer of the sort
+        Self::check_memory::<(Block, HalfKey<W>)>(solutions.len().saturating_mul(2))?;
+        self.progress.phase_start(phase, solutions.len());
+
+        let found = self.pool.run(move |i| {
+            let (first, second) = Self::seeker_range(solutions.len(), i, seekers);
+            let mut found = Vec::new();
+            found
+                .try_reserve_exact((second - first) as usize)
+                .map_err(|_| GostError::OutOfMemory(second - first))?;
+            let mut magma = magma.clone();
+
+            for (n, half_key) in solutions.gray(first..second).enumerate() {
+                if !Self::report_keys(&*progress, &budget, phase, n as u64 + 1) {
+                    return Ok(found);
+                }
+
+                found.push((block(&mut magma, half_key), half_key));
+            }
+            progress.keys_probed(phase, (second - first) % PROGRESS_BATCH);
+
+            Ok(found)
+        })?;
+        let found: Vec<_> = found.into_iter().flatten().collect();
+        self.progress.phase_end(phase);
+
+        Ok(utils::radix_sort(found, Width::<W>::BLOCK_BITS))
     }
 
-    fn _apply_operator(k0: Key) -> Block {
-        (gost_hash::GostHash::psy_pow(gost_hash::GostHash::p_rev(k0), -12) & 0xffff) as u16
+    /// Fails with `GostError::OutOfMemory`, if `n` entries of `T` cannot be allocated,
+    /// so that a round, which does not fit into memory, does not abort the process
+    fn check_memory<T>(n: u64) -> Result<()> {
+        let mut v: Vec<T> = Vec::new();
+        usize::try_from(n)
+            .ok()
+            .and_then(|len| v.try_reserve_exact(len).ok())
+            .ok_or(GostError::OutOfMemory(n))
     }
 
-    fn get_collision(&self) -> Option<(Message, Message)> {
-        let read_lock = self.ctx.fixed_points.read().unwrap();
+    /// Merges the sorted blocks of both sides, every forward solution, which meets a backward one,
+    /// gives a fixed point. Returns the fixed points along with their outputs in the order of the blocks.
+    fn join(
+        &self,
+        forward: Vec<(Block, HalfKey<W>)>,
+        backward: Vec<(Block, HalfKey<W>)>,
+    ) -> Result<Vec<(State<W>, Message<W>)>> {
+        let mut keys = Vec::new();
+        let (mut i, mut j) = (0, 0);
 
-        for (i, m1) in read_lock.iter().enumerate() {
-            for m2 in read_lock.iter().skip(i + 1) {
-                if gost_hash::GostHash::compress(*self.ctx.h, *m1)
-                    == gost_hash::GostHash::compress(*self.ctx.h, *m2)
-                {
-                    return Some((*m1, *m2));
+        while i < forward.len() && j < backward.len() {
+            let block = forward[i].0;
+            if block < backward[j].0 {
+                i += 1;
+                continue;
+            }
+            if block > backward[j].0 {
+                j += 1;
+                continue;
+            }
+
+            let i_end = i + forward[i..].iter().take_while(|(b, _)| *b == block).count();
+            let j_end = j + backward[j..].iter().take_while(|(b, _)| *b == block).count();
+            for (_, first_key_half) in forward[i..i_end].iter() {
+                for (_, second_key_half) in backward[j..j_end].iter() {
+                    // sk7||...||sk0, where first_key_half is sk3||sk2||sk1||sk0
+                    keys.push(Self::join_key(*first_key_half, *second_key_half));
                 }
             }
+            i = i_end;
+            j = j_end;
         }
-        None
-    }
 
-    fn get_operator_values() -> [Block; 64] {
-        let mut ret = [0u16; 64];
-        let mut n = 1u64;
+        // The outputs of the compression function are the slow part, so they are computed by the seekers
+        let h = *self.ctx.h;
+        let params = self.ctx.params;
+        let seekers = self.pool.threads();
+        let found = self.pool.run(move |i| {
+            let (first, second) = Self::seeker_range(keys.len() as u64, i, seekers);
 
-        for a in (&mut ret).iter_mut() {
-            *a = Self::_apply_operator(n);
-            n <<= 1;
+            Ok(keys[first as usize..second as usize]
+                .iter()
+                .map(|key| {
+                    let m = Self::convert_to_message(h, *key);
+                    (GostHash::<W>::compress_with(&params, h, m), m)
+                })
+                .collect::<Vec<_>>())
+        })?;
+
+        Ok(found.into_iter().flatten().collect())
+    }
+
+    /// Reports a batch of keys, once the seeker has probed `probed` keys of its range.
+    /// Returns false, if the seeker should stop.
+    fn report_keys(progress: &dyn ProgressSink, budget: &Budget, phase: Phase, probed: u64) -> bool {
+        if !probed.is_multiple_of(PROGRESS_BATCH) {
+            return true;
         }
+        progress.keys_probed(phase, PROGRESS_BATCH);
+
+        budget.expired().is_none()
+    }
 
-        ret
+    fn convert_to_message(h: State<W>, key: Key<W>) -> Message<W> {
+        GostHash::<W>::p_rev(key) ^ h
     }
 
-    fn apply_operator(b: Arc<[Block; 64]>, k: Key) -> Block {
-        let mut acc = Default::default();
+    /// Returns sk7||...||sk0 given sk3||...||sk0 and sk7||...||sk4
+    fn join_key(low: HalfKey<W>, high: HalfKey<W>) -> Key<W> {
+        Key::<W>::from_limbs(&low.to_limbs()) | (Key::<W>::from_limbs(&high.to_limbs()) << (Width::<W>::STATE_BITS / 2))
+    }
 
-        for i in 0..64 {
-            if (k >> i) & 0b1 == 1 {
-                acc ^= b[i];
+    /// Splits `0..len` into `seekers` chunks and returns the bounds of the `i`-th one
+    fn seeker_range(len: u64, i: usize, seekers: usize) -> (u64, u64) {
+        let step = len / seekers as u64;
+        let first = i as u64 * step;
+        let second = if i == seekers - 1 {
+            len
+        } else {
+            (i as u64 + 1) * step
+        };
+
+        (first, second)
+    }
+
+    fn _apply_operator(k0: Key<W>) -> Block {
+        GostHash::<W>::psy_pow(GostHash::<W>::p_rev(k0), -12).low_u64() & Width::<W>::BLOCK_MASK
+    }
+
+    /// Indexes the fixed point by its compression output and records a collision,
+    /// if another fixed point with the same output has already been found
+    fn add_fixed_point(
+        fixed_points: &mut HashMap<State<W>, Message<W>>,
+        collision: &mut Option<(Message<W>, Message<W>)>,
+        out: State<W>,
+        m: Message<W>,
+    ) {
+        match fixed_points.get(&out) {
+            Some(other) if *other != m => {
+                collision.get_or_insert((*other, m));
+            }
+            Some(_) => {}
+            None => {
+                fixed_points.insert(out, m);
             }
         }
+    }
+
+    /// The operator as a (8 * W)x(32 * W) matrix over GF(2)
+    fn get_operator_matrix() -> gf2::BitMatrix {
+        let rows = Width::<W>::BLOCK_BITS as usize;
 
-        acc
+        gf2::BitMatrix::from_vector_fn(rows, Width::<W>::STATE_BITS as usize, |k| {
+            gf2::BitVector::from_u64(rows, Self::_apply_operator(Key::<W>::from_limbs(k.limbs())))
+        })
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
+    use crate::width::{Word, U256};
     use rand::Rng;
 
     #[test]
     fn operator_application_methods() {
-        let a = Arc::new(super::GostAttack::get_operator_values());
+        let a = super::GostAttack::<2>::get_operator_matrix();
         let mut k;
         let mut rand_gen = rand::thread_rng();
 
         for _ in 0..1280 {
             k = rand_gen.gen();
+            assert_eq!(super::GostAttack::<2>::_apply_operator(k), a.apply_u64(k));
+        }
+
+        let a = super::GostAttack::<8>::get_operator_matrix();
+        for _ in 0..128 {
+            let k = U256::from_limbs(&[rand_gen.gen(), rand_gen.gen(), rand_gen.gen(), rand_gen.gen()]);
+            let image = a.apply(&super::gf2::BitVector::from_limbs(256, &k.to_limbs()));
+            assert_eq!(super::GostAttack::<8>::_apply_operator(k), image.to_u64());
+        }
+    }
+
+    #[test]
+    fn operator_solutions() {
+        let matrix = super::GostAttack::<2>::get_operator_matrix();
+        let mut rand_gen = rand::thread_rng();
+
+        for shift in [0, 32].iter() {
+            let d = rand_gen.gen::<u64>() & 0xffff;
+            let solutions: super::Solutions<2> = matrix
+                .columns(*shift..*shift + 32)
+                .solve(&super::gf2::BitVector::from_u64(16, d))
+                .expect("The operator should be surjective")
+                .into();
+            let mut seen = std::collections::HashSet::new();
+
+            assert_eq!(solutions.len(), 1 << 16, "Unexpected kernel dimension");
+
+            for (n, k) in solutions.gray(0..solutions.len()).enumerate() {
+                assert_eq!(super::GostAttack::<2>::_apply_operator((k as u64) << shift), d);
+                assert!(seen.insert(k), "Solutions should be distinct");
+                assert_eq!(k, solutions.get(n as u64 ^ (n as u64 >> 1)));
+            }
             assert_eq!(
-                super::GostAttack::_apply_operator(k),
-                super::GostAttack::apply_operator(a.clone(), k)
+                solutions.gray(1000..1010).collect::<Vec<_>>(),
+                solutions.gray(0..1010).skip(1000).collect::<Vec<_>>(),
+                "A range should start anywhere"
             );
         }
     }
+
+    #[test]
+    fn same_fixed_point_is_not_a_collision() {
+        use std::collections::HashMap;
+
+        let mut fixed_points = HashMap::new();
+        let mut collision = None;
+        let h = 0x1b1b_1b1b_1b1b_e4e4;
+
+        for m in [42, 42, 43].iter() {
+            let out = crate::gost_hash::GostHash::<2>::compress(h, *m);
+            super::GostAttack::<2>::add_fixed_point(&mut fixed_points, &mut collision, out, *m);
+        }
+
+        assert_eq!(fixed_points.len(), 2);
+        assert_eq!(collision, None);
+    }
+
+    fn one_bit_bytes_collision_with(params: super::ParamSet) {
+        use crate::gost_hash::GostHash;
+
+        let h: Vec<u8> = [1, 0, 1, 1, 1, 0, 1, 1]
+            .iter()
+            .cloned()
+            .chain((0..24).map(|i| (i * 5 % 3 == 0) as u8))
+            .collect();
+        let collision = super::GostAttack::<1>::try_with_params(&h, params)
+            .and_then(|mut attack| attack.generate_collision())
+            .and_then(super::Outcome::into_collision)
+            .expect("The attack should succeed");
+        let h = super::utils::pack::<1, u32>(&h);
+        let (m1, m2) = collision.messages();
+        let (m1, m2) = (super::utils::pack::<1, u32>(&m1), super::utils::pack::<1, u32>(&m2));
+
+        assert_eq!(collision.verify(), Ok(()));
+        assert_eq!((collision.h, collision.m1, collision.m2), (h, m1, m2));
+        assert_ne!(m1, m2, "Messages should differ");
+        assert_eq!(
+            GostHash::<1>::compress_with(&params, h, m1),
+            GostHash::<1>::compress_with(&params, h, m2),
+            "No collision with the {} S-boxes",
+            params.name()
+        );
+    }
+
+    #[test]
+    fn one_bit_bytes_collision() {
+        one_bit_bytes_collision_with(super::ParamSet::Toy);
+    }
+
+    #[test]
+    fn one_bit_bytes_collision_other_sboxes() {
+        one_bit_bytes_collision_with(super::ParamSet::CryptoProA);
+        one_bit_bytes_collision_with(super::ParamSet::Test);
+    }
+
+    #[test]
+    fn state_validation() {
+        use super::GostError;
+
+        let mut h = [1u8; 32];
+        assert!(super::GostAttack::<2>::check_state(&h).is_ok());
+        assert_eq!(super::GostAttack::<2>::check_state(&h[..31]).err(), Some(GostError::WrongLength(31)));
+
+        h[2] = 2;
+        assert_eq!(super::GostAttack::<2>::check_state(&h).err(), Some(GostError::AsymmetricState));
+        h[6] = 2;
+        assert!(super::GostAttack::<2>::check_state(&h).is_ok());
+        assert_eq!(
+            super::GostAttack::<1>::check_state(&h).err(),
+            Some(GostError::ByteOutOfRange { index: 2, value: 2, max: 1 })
+        );
+
+        h[20] = 4;
+        assert!(super::GostAttack::<2>::try_new(&h).is_err());
+
+        assert!(super::GostAttack::<4>::try_new(&[1; 32]).is_ok());
+        assert_eq!(super::GostAttack::<8>::try_new(&[1; 32]).err(), Some(GostError::UnsupportedWidth(8)));
+    }
+
+    #[test]
+    fn round_finds_all_fixed_points() {
+        use super::{gf2, magma, GostAttack, Solutions};
+        use std::collections::HashSet;
+
+        let h = [1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 1];
+        let mut attack = GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, 1).unwrap();
+        let block = attack.ctx.h.low_u64() & 0xff;
+        let mut magma = magma::Magma::<1>::with_params(0, super::ParamSet::Toy);
+        let mut expected = HashSet::new();
+
+        for round in 0..4 {
+            let d1 = round as u64;
+            let forward: Solutions<1> = attack.first_operator.solve(&gf2::BitVector::from_u64(8, d1)).unwrap().into();
+            let backward: Solutions<1> = attack
+                .second_operator
+                .solve(&gf2::BitVector::from_u64(8, d1 ^ *attack.ctx.d))
+                .unwrap()
+                .into();
+            // The first 8 rounds of Magma should keep the symmetric block, then all the 32 rounds keep it
+            for f in 0..forward.len() {
+                for b in 0..backward.len() {
+                    let key = GostAttack::<1>::join_key(forward.get(f), backward.get(b));
+                    magma.rekey(key);
+                    if magma.encrypt_rounds(block, 0..8) == block {
+                        expected.insert(GostAttack::<1>::convert_to_message(*attack.ctx.h, key));
+                    }
+                }
+            }
+            attack.find_fixed_points_round(round).unwrap();
+        }
+
+        let mut found: HashSet<_> = attack.ctx.fixed_points.values().cloned().collect();
+        if let Some((m1, m2)) = attack.ctx.collision {
+            found.insert(m1);
+            found.insert(m2);
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn interrupt_and_resume() {
+        use crate::budget::{Budget, CancelToken, StopReason};
+
+        let path = std::env::temp_dir().join(format!("interrupt_and_resume-{}.ckpt", std::process::id()));
+        let h = [1, 0, 1, 0, 1, 0, 1, 0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 1];
+        let mut attack = super::GostAttack::<1>::try_new(&h).unwrap();
+        for _ in 0..2 {
+            attack.find_fixed_points_round(attack.ctx.round).unwrap();
+            attack.ctx.round += 1;
+        }
+        attack.set_checkpoint(&path, std::time::Duration::from_secs(3600));
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        attack.set_budget(Budget {
+            cancel,
+            ..Default::default()
+        });
+        match attack.generate_collision().unwrap() {
+            super::Outcome::Partial(p) => {
+                assert_eq!(p.reason, StopReason::Cancelled);
+                assert_eq!(p.rounds, 2);
+                assert_eq!(p.fixed_points, attack.ctx.fixed_points);
+            }
+            _ => panic!("The attack should be cancelled"),
+        }
+
+        let resumed = super::GostAttack::<1>::resume(&path).unwrap();
+        assert!(!resumed.ctx.fixed_points.is_empty());
+        assert_eq!(*resumed.ctx.h, *attack.ctx.h);
+        assert_eq!(*resumed.ctx.d, *attack.ctx.d);
+        assert_eq!(resumed.ctx.round, 2);
+        assert_eq!(resumed.ctx.fixed_points, attack.ctx.fixed_points);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn collision_survives_resume() {
+        let path = std::env::temp_dir().join(format!("collision_survives_resume-{}.ckpt", std::process::id()));
+        let h = [0, 1, 1, 0, 0, 1, 1, 0, 1, 1, 1, 0, 0, 1, 0, 1, 0, 0, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 1, 1];
+        let expected = super::GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, 0)
+            .unwrap()
+            .generate_collision()
+            .unwrap();
+
+        // The periodic save right after the round, which finds the collision
+        let mut attack = super::GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, 0).unwrap();
+        while attack.ctx.collision.is_none() {
+            attack.find_fixed_points_round(attack.ctx.round).unwrap();
+            attack.ctx.round += 1;
+        }
+        attack.set_checkpoint(&path, std::time::Duration::from_secs(0));
+        attack.save_checkpoint_if_due().unwrap();
+
+        let mut resumed = super::GostAttack::<1>::resume(&path).unwrap();
+        assert_eq!(resumed.generate_collision().unwrap(), expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn same_seed_same_collision() {
+        let h = [0, 1, 1, 0, 0, 1, 1, 0, 1, 1, 1, 0, 0, 1, 0, 1, 0, 0, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 1, 1];
+        let run = |seed, threads| {
+            let mut attack = super::GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, seed).unwrap();
+            assert_eq!(attack.seed(), Some(seed));
+            attack.set_threads(threads);
+            attack.generate_collision().and_then(super::Outcome::into_collision).unwrap()
+        };
+
+        for seed in [0, 2024].iter() {
+            let collision = run(*seed, 8);
+            for threads in [1, 3, 8].iter() {
+                assert_eq!(run(*seed, *threads), collision, "The collision depends on the scheduling");
+            }
+        }
+    }
+
+    #[test]
+    fn budget_runs_out() {
+        use crate::budget::{Budget, StopReason};
+        use std::time::{Duration, Instant};
+
+        let h = [0, 1, 1, 0, 0, 1, 1, 0, 1, 1, 1, 0, 0, 1, 0, 1, 0, 0, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 1, 1];
+        let mut attack = super::GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, 7).unwrap();
+        let expected = super::GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, 7)
+            .unwrap()
+            .generate_collision()
+            .unwrap();
+
+        attack.set_budget(Budget {
+            deadline: Some(Instant::now() - Duration::from_secs(1)),
+            ..Default::default()
+        });
+        match attack.generate_collision().unwrap() {
+            super::Outcome::Partial(p) => {
+                assert_eq!(p.reason, StopReason::Deadline);
+                assert_eq!(p.rounds, 0);
+                assert!(p.fixed_points.is_empty());
+            }
+            _ => panic!("The deadline has passed"),
+        }
+
+        attack.set_budget(Budget {
+            max_fixed_points: Some(1),
+            ..Default::default()
+        });
+        match attack.generate_collision().unwrap() {
+            super::Outcome::Partial(p) => {
+                assert_eq!(p.reason, StopReason::MemoryCap);
+                assert!(!p.fixed_points.is_empty());
+                assert!(p.rounds > 0);
+            }
+            // The first round with fixed points of this seed gives no collision
+            _ => panic!("The cap of a single fixed point should be hit"),
+        }
+
+        // The stopped attack goes on to the same collision
+        attack.set_budget(Budget::default());
+        assert_eq!(attack.generate_collision().unwrap(), expected);
+    }
+
+    #[test]
+    fn four_bit_bytes_do_not_abort() {
+        use crate::budget::{Budget, StopReason};
+        use std::time::{Duration, Instant};
+
+        // 2^32 keys per side do not fit into memory on most machines, the others run out of time
+        let h: Vec<u8> = (0..32).map(|i| [5, 9, 2, 12][i % 4]).collect();
+        let mut attack = super::GostAttack::<4>::try_with_seed(&h, super::ParamSet::Toy, 1).unwrap();
+        attack.set_budget(Budget {
+            deadline: Some(Instant::now() + Duration::from_secs(2)),
+            ..Default::default()
+        });
+
+        match attack.generate_collision() {
+            Err(super::GostError::OutOfMemory(n)) => assert_eq!(n, 1 << 33),
+            Ok(super::Outcome::Partial(p)) => assert_eq!(p.reason, StopReason::Deadline),
+            _ => panic!("The attack should either run out of memory or of time"),
+        }
+    }
+
+    #[test]
+    fn collision_verification() {
+        use super::{Collision, GostError};
+
+        let h = [1, 0, 1, 1, 1, 0, 1, 1, 0, 0, 1, 0, 1, 1, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 1];
+        let collision = super::GostAttack::<1>::try_with_seed(&h, super::ParamSet::Toy, 5)
+            .and_then(|mut attack| attack.generate_collision())
+            .and_then(super::Outcome::into_collision)
+            .unwrap();
+        assert_eq!(collision.verify(), Ok(()));
+
+        let same = Collision {
+            m2: collision.m1,
+            ..collision.clone()
+        };
+        assert_eq!(same.verify(), Err(GostError::NotACollision));
+
+        let other_sbox = Collision {
+            params: super::ParamSet::Test,
+            ..collision.clone()
+        };
+        assert!(other_sbox.verify().is_err());
+
+        // The messages are found for the given h only
+        let other_h = Collision {
+            h: collision.h ^ 0b1,
+            ..collision.clone()
+        };
+        assert!(other_h.verify().is_err());
+    }
+
+    #[test]
+    fn radix_sort_is_stable() {
+        let mut rand_gen = rand::thread_rng();
+
+        for bits in [8, 16, 64].iter() {
+            let pairs: Vec<(u64, usize)> = (0..10000)
+                .map(|i| (rand_gen.gen::<u64>() >> (64 - bits) >> rand_gen.gen_range(0, 4), i))
+                .collect();
+            let mut expected = pairs.clone();
+            expected.sort_by_key(|(key, _)| *key);
+
+            assert_eq!(super::utils::radix_sort(pairs, *bits), expected);
+        }
+    }
+
+    #[test]
+    fn pack_unpack_roundtrip() {
+        let x: Vec<u8> = (0..32).map(|i| (i * 37 % 16) as u8).collect();
+        let mut y = [0u8; 32];
+
+        super::utils::unpack::<4, u128>(&mut y, super::utils::pack::<4, u128>(&x));
+        assert_eq!(x, y.to_vec());
+    }
 }
@@ -1,65 +1,202 @@
 //! # GOST hash function
-//! This module implements a GOST hash function with a constraint that the one "byte" consists of 2 bits.
-use crate::magma::Magma;
+//! This module implements a GOST hash function with a constraint that the one "byte" consists of `W` bits
+//! (2 by default).
+//! The full-size GOST R 34.11-94 with the test parameters from RFC 5831 is available as `FullGostHash`.
+use crate::magma::{Block, FullMagma, Magma, ParamSet, HASH_TEST_SBOX};
+use crate::width::{State, Storage, Width, Word};
 
-type Block = u16;
-type State = u64;
-type Key = State;
+type Key<const W: u32> = State<W>;
 type SubState = Block;
 
-#[derive(Default)]
-struct IntermediateKeys(Key, Key, Key, Key);
+pub type FullState = [u8; 32];
+
+/// Number of "bytes" in a message block
+static BLOCK_BYTES: usize = 32;
+
+/// The constant C_3 of the key generation in little endian
+static FULL_C: FullState = [
+    0x00, 0xff, 0x00, 0xff, 0x00, 0xff, 0x00, 0xff, 0xff, 0x00, 0xff, 0x00, 0xff, 0x00, 0xff, 0x00,
+    0x00, 0xff, 0xff, 0x00, 0xff, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00, 0xff,
+];
+
+struct IntermediateKeys<const W: u32>(Key<W>, Key<W>, Key<W>, Key<W>)
+where
+    Width<W>: Storage;
 
 #[derive(Default)]
 struct IntermediateState(SubState, SubState, SubState, SubState);
 
-pub struct GostHash {
-    _state: State,
+/// GOST hash function with `W` bit bytes. The message is fed as a sequence of "bytes" from 0 to 2^W - 1.
+pub struct GostHash<const W: u32 = 2>
+where
+    Width<W>: Storage,
+{
+    state: State<W>,
+    /// Sum of the message blocks mod 2^(32 * W)
+    sigma: State<W>,
+    /// Length of the processed message in bits
+    length: State<W>,
+    buffer: Vec<u8>,
+    params: ParamSet,
 }
 
-impl IntermediateState {
-    fn to_state(&self) -> State {
-        let mut s: State = Default::default();
+/// GOST R 34.11-94 hash function with 8 bit bytes
+pub struct FullGostHash {
+    state: FullState,
+    /// Sum of the message blocks mod 2^256
+    sigma: FullState,
+    /// Length of the processed message in bits
+    length: u64,
+    buffer: Vec<u8>,
+}
 
-        s |= self.0 as State;
-        s |= (self.1 as State) << 16;
-        s |= (self.2 as State) << 32;
-        s |= (self.3 as State) << 48;
+impl IntermediateState {
+    fn to_state<const W: u32>(&self) -> State<W>
+    where
+        Width<W>: Storage,
+    {
+        let bits = Width::<W>::BLOCK_BITS;
+        let mut s = State::<W>::default();
+
+        s |= State::<W>::from_u64(self.0);
+        s |= State::<W>::from_u64(self.1) << bits;
+        s |= State::<W>::from_u64(self.2) << (2 * bits);
+        s |= State::<W>::from_u64(self.3) << (3 * bits);
 
         s
     }
 
-    fn from_state(&mut self, s: State){
-        self.0 = s as SubState;
-        self.1 = (s >> 16) as SubState;
-        self.2 = (s >> 32) as SubState;
-        self.3 = (s >> 48) as SubState;
+    fn load_state<const W: u32>(&mut self, s: State<W>)
+    where
+        Width<W>: Storage,
+    {
+        let bits = Width::<W>::BLOCK_BITS;
+        let mask = Width::<W>::BLOCK_MASK;
+
+        self.0 = s.low_u64() & mask;
+        self.1 = (s >> bits).low_u64() & mask;
+        self.2 = (s >> (2 * bits)).low_u64() & mask;
+        self.3 = (s >> (3 * bits)).low_u64() & mask;
+    }
+}
+
+impl<const W: u32> Default for GostHash<W>
+where
+    Width<W>: Storage,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl GostHash {
-    pub fn new() -> GostHash {
-        GostHash { _state: 0 }
+impl<const W: u32> GostHash<W>
+where
+    Width<W>: Storage,
+{
+    pub fn new() -> GostHash<W> {
+        Self::with_iv(State::<W>::default())
+    }
+
+    /// Returns a hasher, which starts from the given initial state
+    pub fn with_iv(h: State<W>) -> GostHash<W> {
+        Self::with_params(h, ParamSet::Toy)
+    }
+
+    /// Returns a hasher, which starts from the given initial state and encrypts with the given S-boxes
+    pub fn with_params(h: State<W>, params: ParamSet) -> GostHash<W> {
+        GostHash {
+            state: h,
+            sigma: State::<W>::default(),
+            length: State::<W>::default(),
+            buffer: Vec::with_capacity(BLOCK_BYTES),
+            params,
+        }
+    }
+
+    /// Feeds the message into the hash
+    /// # Panics
+    /// Panics if any of the "bytes" does not fit into `W` bits.
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data.iter() {
+            assert!(
+                *byte <= Width::<W>::BYTE_MASK,
+                "The elements of the message should be from 0 to {}",
+                Width::<W>::BYTE_MASK
+            );
+            self.buffer.push(*byte);
+
+            if self.buffer.len() == BLOCK_BYTES {
+                let m = Self::pack_block(&self.buffer);
+                self.buffer.clear();
+                self.process_block(m, BLOCK_BYTES as u64 * W as u64);
+            }
+        }
+    }
+
+    /// Pads the last block with zeros, then processes the length and the checksum blocks
+    pub fn finalize(mut self) -> State<W> {
+        if !self.buffer.is_empty() {
+            let m = Self::pack_block(&self.buffer);
+            self.process_block(m, self.buffer.len() as u64 * W as u64);
+        }
+
+        let h = Self::compress_with(&self.params, self.state, self.length);
+        Self::compress_with(&self.params, h, self.sigma)
+    }
+
+    /// Returns the hash of the whole message
+    pub fn digest(data: &[u8]) -> State<W> {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    fn process_block(&mut self, m: State<W>, bits: u64) {
+        self.state = Self::compress_with(&self.params, self.state, m);
+        self.sigma = self.sigma.wrapping_add(m);
+        self.length = self.length.wrapping_add(State::<W>::from_u64(bits));
+    }
+
+    /// The first "byte" goes to the lowest bits, missing "bytes" are zero
+    fn pack_block(bytes: &[u8]) -> State<W> {
+        bytes
+            .iter()
+            .enumerate()
+            .fold(State::<W>::default(), |acc, (i, b)| {
+                acc | (State::<W>::from_u64(*b as u64) << (i as u32 * W))
+            })
     }
 
     /// Gost compression function.
     /// **Takes** a state and a message block as input and **returns** the next state. Both are of size 32 bytes.
-    pub fn compress(h: State, m: State) -> State {
+    pub fn compress(h: State<W>, m: State<W>) -> State<W> {
+        Self::compress_with(&ParamSet::Toy, h, m)
+    }
+
+    /// Gost compression function, where Magma uses the S-boxes of the given parameter set
+    pub fn compress_with(params: &ParamSet, h: State<W>, m: State<W>) -> State<W> {
         let k = Self::key_gen(h, m);
         let mut s: IntermediateState = Default::default();
 
-        s.from_state(h);
-        
-        s.0 = Magma::new(k.0).encrypt_block(s.0);
-        s.1 = Magma::new(k.1).encrypt_block(s.1);
-        s.2 = Magma::new(k.2).encrypt_block(s.2);
-        s.3 = Magma::new(k.3).encrypt_block(s.3);
+        s.load_state::<W>(h);
 
-        Self::output_transformation(s.to_state(), h, m)
+        s.0 = Magma::<W>::with_params(k.0, *params).encrypt_block(s.0);
+        s.1 = Magma::<W>::with_params(k.1, *params).encrypt_block(s.1);
+        s.2 = Magma::<W>::with_params(k.2, *params).encrypt_block(s.2);
+        s.3 = Magma::<W>::with_params(k.3, *params).encrypt_block(s.3);
+
+        Self::output_transformation(s.to_state::<W>(), h, m)
     }
 
-    fn key_gen(h: State, m: State) -> IntermediateKeys {
-        let c = 0b1100111100000011110000110011110000110011001100111100110011001100;
+    /// Returns the four keys of Magma, which the compression function derives from the state and the message
+    pub fn keys(h: State<W>, m: State<W>) -> [Key<W>; 4] {
+        let k = Self::key_gen(h, m);
+
+        [k.0, k.1, k.2, k.3]
+    }
+
+    fn key_gen(h: State<W>, m: State<W>) -> IntermediateKeys<W> {
+        let c = Self::c();
         let mut cur_h = h;
         let mut cur_m = m;
 
@@ -71,7 +208,7 @@ impl GostHash {
         cur_m = Self::a(Self::a(cur_m));
         let k1 = Self::p(cur_h ^ cur_m);
 
-        // Step 3. Here c == that thig on top (0xff -> 0x03 as we have only 2 bits)
+        // Step 3. Here c == C_3
         cur_h = Self::a(cur_h) ^ c;
         cur_m = Self::a(Self::a(cur_m));
         let k2 = Self::p(cur_h ^ cur_m);
@@ -84,23 +221,36 @@ impl GostHash {
         IntermediateKeys(k0, k1, k2, k3)
     }
 
-    fn p(x: State) -> Key {
-        let mut k = 0;
+    /// The constant C_3, where 0xff bytes are replaced with `W` bit "bytes" of ones
+    fn c() -> State<W> {
+        FULL_C
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b != 0)
+            .fold(State::<W>::default(), |acc, (i, _)| acc | (State::<W>::mask(W) << (i as u32 * W)))
+    }
 
-        for i in 1..32 {
+    fn byte(x: State<W>, i: usize) -> State<W> {
+        (x >> (i as u32 * W)) & State::<W>::mask(W)
+    }
+
+    pub fn p(x: State<W>) -> Key<W> {
+        let mut k = State::<W>::default();
+
+        for i in 1..=32 {
             // k[i - 1] = x[phi(i) - 1]
-            k |= ((x >> ((Self::phi(i)-1) << 1)) & 0b11) << ((i-1) << 1);
+            k |= Self::byte(x, Self::phi(i) - 1) << ((i - 1) as u32 * W);
         }
 
         k
     }
 
-    pub fn p_rev(k: Key) -> State {
-        let mut x = 0;
+    pub fn p_rev(k: Key<W>) -> State<W> {
+        let mut x = State::<W>::default();
 
-        for i in 1..32 {
-           // x[phi(i) - 1] = k[i - 1];
-           x |= ((k >> ((i-1) << 1)) & 0b11) << ((Self::phi(i)-1) << 1);
+        for i in 1..=32 {
+            // x[phi(i) - 1] = k[i - 1];
+            x |= Self::byte(k, i - 1) << ((Self::phi(i) - 1) as u32 * W);
         }
 
         x
@@ -114,40 +264,51 @@ impl GostHash {
         8 * i + k
     }
 
-    fn a(x: State) -> State {
+    pub fn a(x: State<W>) -> State<W> {
         // x = y4 || y3 || y2 || y1
-        let mut s: State = Default::default();
+        let bits = Width::<W>::BLOCK_BITS;
+        let mask = State::<W>::mask(bits);
+        let mut s = State::<W>::default();
 
-        s |= x >> 16; // y4 || y3 || y2
-        s |= ((x & 0xffff) ^ (s & 0xffff)) << 48; // (y1 xor y2) || y4 || y3 || y2
+        s |= x >> bits; // y4 || y3 || y2
+        s |= ((x & mask) ^ (s & mask)) << (3 * bits); // (y1 xor y2) || y4 || y3 || y2
 
         s
     }
 
-    fn psy(x: State) -> State {
-        let mut s: State = Default::default(); 
+    /// Returns the `j`-th word of `2 * W` bits
+    fn gamma(x: State<W>, j: u32) -> State<W> {
+        (x >> (j * 2 * W)) & State::<W>::mask(2 * W)
+    }
+
+    pub fn psy(x: State<W>) -> State<W> {
+        let mut s = State::<W>::default();
 
-        s |= x >> 4; // ? || gamma15 || .. || gamma1 
+        s |= x >> (2 * W); // ? || gamma15 || .. || gamma1
         // gamma0 ^ gamma1 ^ gamma2 ^ gamma3 ^ gamma12 ^ gamma15
-        let acc = (x & 0xf) ^ ((x >> 4) & 0xf) ^ ((x >> 8) & 0xf) ^ ((x >> 12) & 0xf) ^ ((x >> 48) & 0xf) ^ ((x >> 60) & 0xf); 
-        s |= acc << 60; // (XOR) || gamma15 || .. || gamma1 
-        
+        let acc = [0, 1, 2, 3, 12, 15]
+            .iter()
+            .fold(State::<W>::default(), |acc, j| acc ^ Self::gamma(x, *j));
+        s |= acc << (30 * W); // (XOR) || gamma15 || .. || gamma1
+
         s
     }
 
-    fn psy_rev(x: State) -> State {
-        let mut s: State = Default::default();
+    pub fn psy_rev(x: State<W>) -> State<W> {
+        let mut s = State::<W>::default();
 
-        s |= x << 4; // gamma15 || .. || gamma1
-        let sum = x >> 60; // gamma0 ^ gamma1 ^ gamma2 ^ gamma3 ^ gamma12 ^ gamma15
-        // gamma1 ^ gamma2 ^ gamma3 ^ gamma12 ^ gamma15
-        let gamma0 = sum ^ (x & 0xf) ^ ((x >> 4) & 0xf) ^ ((x >> 8) & 0xf) ^ ((x >> 44) & 0xf) ^ ((x >> 56) & 0xf);
+        s |= x << (2 * W); // gamma15 || .. || gamma1
+        let sum = Self::gamma(x, 15); // gamma0 ^ gamma1 ^ gamma2 ^ gamma3 ^ gamma12 ^ gamma15
+        // gamma1 ^ gamma2 ^ gamma3 ^ gamma12 ^ gamma15, which are shifted by one in x
+        let gamma0 = [0, 1, 2, 11, 14]
+            .iter()
+            .fold(sum, |acc, j| acc ^ Self::gamma(x, *j));
         s |= gamma0;
-        
+
         s
     }
 
-    pub fn psy_pow(x: State, n: i32) -> State {
+    pub fn psy_pow(x: State<W>, n: i32) -> State<W> {
         let mut tmp = x;
 
         if n >= 0 {
@@ -163,8 +324,284 @@ impl GostHash {
         tmp
     }
 
-    fn output_transformation(s: State, h: State, m: State) -> State {
+    fn output_transformation(s: State<W>, h: State<W>, m: State<W>) -> State<W> {
         // h_i = psy^61(h_i-1 xor psy(m xor psy^12(s)))
         Self::psy_pow(h ^ Self::psy(m ^ Self::psy_pow(s, 12)), 61)
     }
 }
+
+impl Default for FullGostHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FullGostHash {
+    pub fn new() -> FullGostHash {
+        Self::with_iv([0; 32])
+    }
+
+    /// Returns a hasher, which starts from the given initial state
+    pub fn with_iv(h: FullState) -> FullGostHash {
+        FullGostHash {
+            state: h,
+            sigma: [0; 32],
+            length: 0,
+            buffer: Vec::with_capacity(BLOCK_BYTES),
+        }
+    }
+
+    /// Feeds the message into the hash
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data.iter() {
+            self.buffer.push(*byte);
+
+            if self.buffer.len() == BLOCK_BYTES {
+                let mut m = [0; 32];
+                m.copy_from_slice(&self.buffer);
+                self.buffer.clear();
+                self.process_block(&m, (BLOCK_BYTES << 3) as u64);
+            }
+        }
+    }
+
+    /// Pads the last block with zeros, then processes the length and the checksum blocks
+    pub fn finalize(mut self) -> FullState {
+        if !self.buffer.is_empty() {
+            let mut m = [0; 32];
+            m[..self.buffer.len()].copy_from_slice(&self.buffer);
+            self.process_block(&m, (self.buffer.len() << 3) as u64);
+        }
+
+        let mut length = [0; 32];
+        length[..8].copy_from_slice(&self.length.to_le_bytes());
+
+        let h = Self::compress(&self.state, &length);
+        Self::compress(&h, &self.sigma)
+    }
+
+    /// Returns the hash of the whole message
+    pub fn digest(data: &[u8]) -> FullState {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    fn process_block(&mut self, m: &FullState, bits: u64) {
+        self.state = Self::compress(&self.state, m);
+        self.length = self.length.wrapping_add(bits);
+
+        let mut carry = 0;
+        for (s, b) in self.sigma.iter_mut().zip(m.iter()) {
+            let sum = *s as u16 + *b as u16 + carry;
+            *s = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+
+    /// Gost compression function.
+    /// **Takes** a state and a message block as input and **returns** the next state. Both are little endian.
+    pub fn compress(h: &FullState, m: &FullState) -> FullState {
+        let k = Self::key_gen(h, m);
+        let mut s = [0; 32];
+
+        for (i, key) in k.iter().enumerate() {
+            let mut words = [0; 8];
+            for (w, bytes) in words.iter_mut().zip(key.chunks(4)) {
+                *w = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+
+            let mut block = [0; 8];
+            block.copy_from_slice(&h[i << 3..(i + 1) << 3]);
+            let block = FullMagma::with_sbox(words, HASH_TEST_SBOX).encrypt_block(u64::from_le_bytes(block));
+            s[i << 3..(i + 1) << 3].copy_from_slice(&block.to_le_bytes());
+        }
+
+        // h_i = psy^61(h_i-1 xor psy(m xor psy^12(s)))
+        let mut x = Self::psy_pow(&s, 12);
+        Self::xor(&mut x, m);
+        let mut x = Self::psy(&x);
+        Self::xor(&mut x, h);
+
+        Self::psy_pow(&x, 61)
+    }
+
+    fn key_gen(h: &FullState, m: &FullState) -> [FullState; 4] {
+        let mut keys = [[0; 32]; 4];
+        let mut cur_h = *h;
+        let mut cur_m = *m;
+
+        for (j, k) in keys.iter_mut().enumerate() {
+            if j > 0 {
+                cur_h = Self::a(&cur_h);
+                cur_m = Self::a(&Self::a(&cur_m));
+            }
+            // Only C_3 is not zero
+            if j == 2 {
+                Self::xor(&mut cur_h, &FULL_C);
+            }
+
+            let mut w = cur_h;
+            Self::xor(&mut w, &cur_m);
+            *k = Self::p(&w);
+        }
+
+        keys
+    }
+
+    fn xor(x: &mut FullState, y: &FullState) {
+        for (a, b) in x.iter_mut().zip(y.iter()) {
+            *a ^= b;
+        }
+    }
+
+    fn p(x: &FullState) -> FullState {
+        let mut k = [0; 32];
+
+        for i in 1..=32 {
+            k[i - 1] = x[GostHash::<8>::phi(i) - 1];
+        }
+
+        k
+    }
+
+    fn a(x: &FullState) -> FullState {
+        // x = y4 || y3 || y2 || y1
+        let mut s = [0; 32];
+
+        s[..24].copy_from_slice(&x[8..]); // y4 || y3 || y2
+        for i in 0..8 {
+            s[24 + i] = x[i] ^ x[8 + i]; // (y1 xor y2) || y4 || y3 || y2
+        }
+
+        s
+    }
+
+    fn psy(x: &FullState) -> FullState {
+        let mut s = [0; 32];
+
+        s[..30].copy_from_slice(&x[2..]); // ? || gamma15 || .. || gamma1
+        // gamma0 ^ gamma1 ^ gamma2 ^ gamma3 ^ gamma12 ^ gamma15
+        for gamma in [0, 1, 2, 3, 12, 15].iter() {
+            s[30] ^= x[gamma << 1];
+            s[31] ^= x[(gamma << 1) + 1];
+        }
+
+        s
+    }
+
+    fn psy_pow(x: &FullState, n: usize) -> FullState {
+        let mut tmp = *x;
+
+        for _ in 0..n {
+            tmp = Self::psy(&tmp);
+        }
+
+        tmp
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FullGostHash, GostHash};
+    use crate::gf2::BitMatrix;
+    use crate::width::{State, Storage, Width, Word};
+
+    #[test]
+    fn p_rev_is_inverse_of_p() {
+        let p = BitMatrix::from_fn(64, 64, GostHash::<2>::p);
+        let p_rev = BitMatrix::from_fn(64, 64, GostHash::<2>::p_rev);
+
+        assert_eq!(p.inverse(), Some(p_rev), "P^-1 does not match p_rev");
+    }
+
+    #[test]
+    fn psy_rev_is_inverse_of_psy() {
+        let psy = BitMatrix::from_fn(64, 64, GostHash::<2>::psy);
+        let psy_rev = BitMatrix::from_fn(64, 64, GostHash::<2>::psy_rev);
+
+        assert_eq!(psy.inverse(), Some(psy_rev), "Psy^-1 does not match psy_rev");
+        assert_eq!(
+            BitMatrix::from_fn(64, 64, |x| GostHash::<2>::psy_pow(x, 61)),
+            psy.inverse().unwrap().compose(&BitMatrix::from_fn(64, 64, |x| GostHash::<2>::psy_pow(x, 62)))
+        );
+    }
+
+    fn p_rev_is_inverse_of_p_for<const W: u32>()
+    where
+        Width<W>: Storage,
+    {
+        let x = State::<W>::from_limbs(&[0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210, !0, 0x5555]);
+        assert_eq!(GostHash::<W>::p_rev(GostHash::<W>::p(x)), x, "p_rev does not invert p for W = {}", W);
+        assert_eq!(GostHash::<W>::psy_rev(GostHash::<W>::psy(x)), x, "psy_rev does not invert psy for W = {}", W);
+    }
+
+    #[test]
+    fn inverses_all_widths() {
+        p_rev_is_inverse_of_p_for::<1>();
+        p_rev_is_inverse_of_p_for::<2>();
+        p_rev_is_inverse_of_p_for::<4>();
+        p_rev_is_inverse_of_p_for::<8>();
+    }
+
+    #[test]
+    fn a_is_invertible() {
+        assert_eq!(BitMatrix::from_fn(64, 64, GostHash::<2>::a).rank(), 64);
+    }
+
+    #[test]
+    fn streaming_matches_definition() {
+        let message: Vec<u8> = (0..45).map(|i| (i * 7 % 4) as u8).collect();
+        let mut hasher = GostHash::<2>::new();
+        for chunk in message.chunks(5) {
+            hasher.update(chunk);
+        }
+
+        // One full block, then 13 "bytes" padded with zeros
+        let m1 = GostHash::<2>::pack_block(&message[..32]);
+        let m2 = GostHash::<2>::pack_block(&message[32..]);
+        let mut h = GostHash::<2>::compress(GostHash::<2>::compress(0, m1), m2);
+        h = GostHash::<2>::compress(h, 90);
+        h = GostHash::<2>::compress(h, m1.wrapping_add(m2));
+
+        assert_eq!(hasher.finalize(), h, "Streaming hash does not match the definition");
+    }
+
+    fn hex(x: &[u8]) -> String {
+        x.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // Test vectors from RFC 5831, section 7.3.1
+    #[test]
+    fn full_hash_test_vectors() {
+        let vectors: [(&[u8], &str); 6] = [
+            (b"", "ce85b99cc46752fffee35cab9a7b0278abb4c2d2055cff685af4912c49490f8d"),
+            (b"abc", "f3134348c44fb1b2a277729e2285ebb5cb5e0f29c975bc753b70497c06a4d51d"),
+            (b"message digest", "ad4434ecb18f2c99b60cbe59ec3d2469582b65273f48de72db2fde16a4889a4d"),
+            (
+                b"This is message, length=32 bytes",
+                "b1c466d37519b82e8319819ff32595e047a28cb6f83eff1c6916a815a637fffa",
+            ),
+            (
+                b"Suppose the original message has length = 50 bytes",
+                "471aba57a60a770d3a76130635c1fbea4ef14de51f78b4ae57dd893b62f55208",
+            ),
+            (&[b'U'; 128], "53a3a3ed25180cef0c1d85a074273e551c25660a87062a52d926a9e8fe5733a4"),
+        ];
+
+        for (message, expected) in vectors.iter() {
+            assert_eq!(hex(&FullGostHash::digest(message)), *expected, "Hash of {:?} is wrong", message);
+        }
+    }
+
+    #[test]
+    fn full_hash_streaming() {
+        let message = [b'a'; 1000];
+        let mut hasher = FullGostHash::new();
+        for chunk in message.chunks(33) {
+            hasher.update(chunk);
+        }
+
+        assert_eq!(hasher.finalize(), FullGostHash::digest(&message));
+    }
+}
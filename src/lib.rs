@@ -1,3 +1,16 @@
+// The crate is named after the GOST standard, hence the upper case
+#![allow(non_snake_case)]
+
+pub mod budget;
+pub mod error;
+pub mod format;
+pub mod gf2;
+pub mod width;
 pub mod magma;
+pub mod bitslice;
 pub mod gost_hash;
-pub mod gost_collision;
\ No newline at end of file
+pub mod gost_collision;
+pub mod checkpoint;
+pub mod certificate;
+pub mod progress;
+pub mod pool;
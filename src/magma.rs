@@ -1,174 +1,688 @@
 //! # Magma
-//! This module implements the GOST block cipher Magma, but uses 2 bit bytes in oreder to
-//! make the attack feasible on PC
-
+//! This module implements the GOST block cipher Magma, but uses `W` bit bytes (2 by default) in oreder to
+//! make the attack feasible on PC.
+//! The full-size cipher from GOST R 34.12-2015 is available as `FullMagma` to check the toy results against.
+use crate::error::{GostError, Result};
+use crate::width::{State, Storage, Width, Word};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+static SBOX_1: [u8; 2] = [1, 0];
 static SBOX: [u8; 4] = [1, 3, 0, 2];
 
-type HalfBlock = u8;
-type Block = u16;
-type Key = u64;
-type RoundKey = u8;
+/// S-boxes pi_0..pi_7 from GOST R 34.12-2015, pi_0 is applied to the lowest 4 bits
+static FULL_SBOX: [[u8; 16]; 8] = [
+    [12, 4, 6, 2, 10, 5, 11, 9, 14, 8, 13, 7, 0, 3, 15, 1],
+    [6, 8, 2, 3, 9, 10, 5, 12, 1, 14, 4, 7, 11, 13, 0, 15],
+    [11, 3, 5, 8, 2, 15, 10, 13, 14, 1, 7, 4, 12, 9, 6, 0],
+    [12, 8, 2, 1, 13, 4, 15, 6, 7, 0, 10, 5, 3, 14, 9, 11],
+    [7, 15, 5, 10, 8, 1, 6, 13, 0, 9, 3, 14, 11, 4, 2, 12],
+    [5, 13, 15, 6, 9, 2, 12, 10, 11, 7, 8, 1, 4, 3, 14, 0],
+    [8, 14, 2, 5, 6, 9, 1, 12, 15, 4, 11, 0, 13, 10, 3, 7],
+    [1, 7, 14, 13, 0, 5, 8, 3, 4, 15, 10, 6, 9, 12, 11, 2],
+];
+
+/// S-boxes K1..K8 of id-GostR3411-94-TestParamSet (RFC 5831), K1 is applied to the lowest 4 bits
+pub static HASH_TEST_SBOX: [[u8; 16]; 8] = [
+    [4, 10, 9, 2, 13, 8, 0, 14, 6, 11, 1, 12, 7, 15, 5, 3],
+    [14, 11, 4, 12, 6, 13, 15, 10, 2, 3, 8, 1, 0, 7, 5, 9],
+    [5, 8, 1, 13, 10, 3, 4, 2, 14, 15, 12, 7, 6, 0, 9, 11],
+    [7, 13, 10, 1, 0, 8, 9, 15, 14, 4, 6, 12, 11, 2, 5, 3],
+    [6, 12, 7, 1, 5, 15, 13, 8, 4, 10, 9, 14, 0, 3, 11, 2],
+    [4, 11, 10, 0, 7, 2, 1, 13, 3, 6, 8, 5, 9, 12, 15, 14],
+    [13, 11, 4, 1, 3, 15, 5, 9, 0, 10, 14, 7, 6, 8, 2, 12],
+    [1, 15, 13, 0, 5, 7, 10, 4, 9, 2, 3, 14, 6, 11, 8, 12],
+];
+
+/// S-boxes K1..K8 of id-Gost28147-89-TestParamSet (RFC 4357)
+static TEST_SBOX: [[u8; 16]; 8] = [
+    [4, 2, 15, 5, 9, 1, 0, 8, 14, 3, 11, 12, 13, 7, 10, 6],
+    [12, 9, 15, 14, 8, 1, 3, 10, 2, 7, 4, 13, 6, 0, 11, 5],
+    [13, 8, 14, 12, 7, 3, 9, 10, 1, 5, 2, 4, 6, 15, 0, 11],
+    [14, 9, 11, 2, 5, 15, 7, 1, 0, 13, 12, 6, 10, 4, 3, 8],
+    [3, 14, 5, 9, 6, 8, 0, 13, 10, 11, 7, 12, 2, 1, 15, 4],
+    [8, 15, 6, 11, 1, 9, 12, 5, 13, 3, 7, 10, 0, 14, 2, 4],
+    [9, 11, 12, 0, 3, 6, 7, 5, 4, 8, 14, 15, 1, 10, 2, 13],
+    [12, 6, 5, 2, 11, 0, 9, 13, 3, 14, 7, 10, 15, 4, 1, 8],
+];
+
+/// S-boxes K1..K8 of id-Gost28147-89-CryptoPro-A-ParamSet (RFC 4357)
+static CRYPTOPRO_A_SBOX: [[u8; 16]; 8] = [
+    [9, 6, 3, 2, 8, 11, 1, 7, 10, 4, 14, 15, 12, 0, 13, 5],
+    [3, 7, 14, 9, 8, 10, 15, 0, 5, 2, 6, 12, 11, 4, 13, 1],
+    [14, 4, 6, 2, 11, 3, 13, 8, 12, 15, 5, 10, 0, 7, 1, 9],
+    [14, 7, 10, 12, 13, 1, 3, 9, 0, 2, 11, 4, 15, 8, 5, 6],
+    [11, 5, 1, 9, 8, 13, 15, 0, 14, 4, 2, 3, 12, 7, 10, 6],
+    [3, 10, 13, 12, 1, 2, 0, 11, 7, 5, 9, 4, 8, 15, 14, 6],
+    [1, 13, 2, 9, 7, 10, 6, 0, 8, 12, 4, 5, 15, 3, 11, 14],
+    [11, 10, 15, 5, 0, 12, 14, 8, 6, 2, 3, 9, 1, 7, 13, 4],
+];
+
+/// S-boxes K1..K8 of id-Gost28147-89-CryptoPro-B-ParamSet (RFC 4357)
+static CRYPTOPRO_B_SBOX: [[u8; 16]; 8] = [
+    [8, 4, 11, 1, 3, 5, 0, 9, 2, 14, 10, 12, 13, 6, 7, 15],
+    [0, 1, 2, 10, 4, 13, 5, 12, 9, 7, 3, 15, 11, 8, 6, 14],
+    [14, 12, 0, 10, 9, 2, 13, 11, 7, 5, 8, 15, 3, 6, 1, 4],
+    [7, 5, 0, 13, 11, 6, 1, 2, 3, 10, 12, 15, 4, 14, 9, 8],
+    [2, 7, 12, 15, 9, 5, 10, 11, 1, 4, 0, 13, 6, 8, 14, 3],
+    [8, 3, 2, 6, 4, 13, 14, 11, 12, 1, 7, 15, 10, 0, 9, 5],
+    [5, 2, 10, 11, 9, 1, 12, 3, 7, 4, 13, 0, 6, 15, 8, 14],
+    [0, 4, 11, 14, 8, 3, 7, 1, 10, 2, 9, 6, 15, 13, 5, 12],
+];
+
+/// S-boxes K1..K8 of id-Gost28147-89-CryptoPro-C-ParamSet (RFC 4357)
+static CRYPTOPRO_C_SBOX: [[u8; 16]; 8] = [
+    [1, 11, 12, 2, 9, 13, 0, 15, 4, 5, 8, 14, 10, 7, 6, 3],
+    [0, 1, 7, 13, 11, 4, 5, 2, 8, 14, 15, 12, 9, 10, 6, 3],
+    [8, 2, 5, 0, 4, 9, 15, 10, 3, 7, 12, 13, 6, 14, 1, 11],
+    [3, 6, 0, 1, 5, 13, 10, 8, 11, 2, 9, 7, 14, 15, 12, 4],
+    [8, 13, 11, 0, 4, 5, 1, 2, 9, 3, 12, 14, 6, 15, 10, 7],
+    [12, 9, 11, 1, 8, 14, 2, 4, 7, 3, 6, 5, 10, 0, 15, 13],
+    [10, 9, 6, 8, 13, 14, 2, 0, 15, 3, 5, 11, 4, 1, 12, 7],
+    [7, 4, 0, 5, 10, 2, 15, 14, 12, 6, 1, 11, 13, 9, 3, 8],
+];
+
+/// S-boxes K1..K8 of id-Gost28147-89-CryptoPro-D-ParamSet (RFC 4357)
+static CRYPTOPRO_D_SBOX: [[u8; 16]; 8] = [
+    [15, 12, 2, 10, 6, 4, 5, 0, 7, 9, 14, 13, 1, 11, 8, 3],
+    [11, 6, 3, 4, 12, 15, 14, 2, 7, 13, 8, 0, 5, 10, 9, 1],
+    [1, 12, 11, 0, 15, 14, 6, 5, 10, 13, 4, 8, 9, 3, 7, 2],
+    [1, 5, 14, 12, 10, 7, 0, 13, 6, 2, 11, 4, 9, 3, 15, 8],
+    [0, 12, 8, 9, 13, 2, 10, 11, 7, 3, 6, 5, 4, 14, 15, 1],
+    [8, 0, 15, 3, 2, 5, 14, 11, 1, 10, 4, 7, 12, 9, 13, 6],
+    [3, 0, 6, 15, 1, 14, 9, 2, 13, 8, 12, 4, 11, 10, 5, 7],
+    [1, 10, 6, 8, 15, 11, 0, 4, 12, 3, 5, 9, 7, 13, 2, 14],
+];
+
+/// Holds 4 "bytes", only the lower `4 * W` bits are used
+pub type HalfBlock = u32;
+/// Holds 8 "bytes", only the lower `8 * W` bits are used
+pub type Block = u64;
+pub type RoundKey = HalfBlock;
+
+type FullHalfBlock = u32;
+pub type FullBlock = u64;
+pub type FullKey = [u8; 32];
+pub type FullRoundKey = u32;
+pub type FullSBox = [[u8; 16]; 8];
+
+/// A user-supplied S-box, every row of which is a permutation of 0..16
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CustomSBox(FullSBox);
+
+/// S-box parameter set of Magma.
+/// The rows of the tables are applied to the chunks of 4 bits, the first row to the lowest chunk.
+/// With 1 and 2 bit "bytes" the half-block has 1 and 2 such chunks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ParamSet {
+    /// The 1 and 2 bit S-boxes for 1 and 2 bit "bytes", GOST R 34.12-2015 otherwise
+    #[default]
+    Toy,
+    /// GOST R 34.12-2015, also known as id-tc26-gost-28147-param-Z
+    Gost2015,
+    CryptoProA,
+    CryptoProB,
+    CryptoProC,
+    CryptoProD,
+    /// id-Gost28147-89-TestParamSet from RFC 4357
+    Test,
+    Custom(CustomSBox),
+}
+
+/// The number of rounds of Magma
+pub const ROUNDS: usize = 32;
 
-struct MagmaKey {
-    key: Key,
+/// The key split into sk0..sk7
+struct MagmaKey<const W: u32> {
+    key: [RoundKey; 8],
 }
 
-struct MagmaKeyScheduler<'a> {
-    magma_key: &'a MagmaKey,
-    round_num: usize,
+/// The round keys of `rounds` rounds. The last 8 rounds take sk7..sk0, the ones before them
+/// go through sk0..sk7 over and over. With 32 rounds this is the schedule of the standard.
+struct MagmaKeyScheduler<'a, const W: u32> {
+    magma_key: &'a MagmaKey<W>,
+    rounds: usize,
+    /// The next round from the start
+    front: usize,
+    /// The round after the next one from the end
+    back: usize,
 }
 
-struct MagmaState {
-    left: HalfBlock,
-    right: HalfBlock,
+/// A table per 8 bit chunk of the half-block
+type ChunkTables = [[HalfBlock; 256]; 4];
+
+/// The S-boxes and the rotation of the round function, precomputed for a parameter set.
+/// The S-boxes work on the chunks separately and the rotation is linear, so the output is the XOR
+/// of the outputs of the chunks. With `W <= 2` the half-block is a single chunk, so a round takes
+/// a single lookup after the key addition.
+#[derive(Clone)]
+pub struct RoundTable<const W: u32> {
+    chunks: Arc<ChunkTables>,
 }
 
-/// A GOST block cipher. Works on 8 byte blocks. In this implementation the "byte" is 2 bits long
-pub struct Magma {
-    key: MagmaKey,
-    state: MagmaState,
+thread_local! {
+    /// The tables built by the thread, so that a new instance of Magma does not build them again
+    static ROUND_TABLES: RefCell<HashMap<(u32, ParamSet), Arc<ChunkTables>>> = RefCell::new(HashMap::new());
+}
+
+/// A GOST block cipher. Works on 8 byte blocks. In this implementation the "byte" is `W` bits long.
+/// The key schedule is expanded at construction and the cipher keeps no other state,
+/// so a single instance can be shared by many threads.
+#[derive(Clone)]
+pub struct Magma<const W: u32 = 2> {
+    round_keys: Vec<RoundKey>,
+    table: RoundTable<W>,
+}
+
+/// The GOST R 34.12-2015 block cipher Magma with 8 bit bytes: 64 bit block and 256 bit key
+pub struct FullMagma {
+    round_keys: [FullRoundKey; 32],
+    sbox: FullSBox,
 }
 
 pub mod utils {
     use super::*;
 
-    pub fn s_box(x: HalfBlock) -> HalfBlock {
+    /// Applies the S-boxes of the parameter set, the chunk `i` goes through the row `i`.
+    /// The toy set works on the chunks of `min(W, 4)` bits, the others on the chunks of 4 bits.
+    pub fn s_box<const W: u32>(params: &ParamSet, x: HalfBlock) -> HalfBlock {
+        let toy = *params == ParamSet::Toy && W < 4;
+        let bits = sbox_bits::<W>(params);
+        let mask = (1 << bits) - 1;
+        let table = params.sbox();
+        let mut ret = 0;
+
+        for i in 0..Width::<W>::HALF_BLOCK_BITS / bits {
+            let shift = i * bits;
+            let y = ((x >> shift) & mask) as usize;
+            let s = match (toy, W) {
+                (true, 1) => SBOX_1[y],
+                (true, _) => SBOX[y],
+                (false, _) => table[i as usize][y],
+            };
+            ret |= (s as HalfBlock) << shift;
+        }
+
+        ret
+    }
+
+    /// The round function without the key addition: the S-boxes and the rotation, computed directly.
+    /// `RoundTable` gives the same, but faster.
+    pub fn g<const W: u32>(params: &ParamSet, x: HalfBlock) -> HalfBlock {
+        rotate_left::<W>(s_box::<W>(params, x), Width::<W>::ROTATION)
+    }
+
+    /// The index `i` of the subkey sk_i, which the round takes out of `rounds`, see `MagmaKeyScheduler`
+    pub fn subkey(rounds: usize, round: usize) -> usize {
+        let from_end = rounds - 1 - round;
+        if from_end < 8 {
+            from_end
+        } else {
+            round & 0b111
+        }
+    }
+
+    /// The size of the chunks, which go through the S-boxes
+    pub fn sbox_bits<const W: u32>(params: &ParamSet) -> u32 {
+        if *params == ParamSet::Toy && W < 4 {
+            Width::<W>::SBOX_BITS
+        } else {
+            4
+        }
+    }
+
+    /// Checks that the row of an S-box is a permutation of 0..16
+    pub fn is_permutation(row: &[u8; 16]) -> bool {
+        let mut seen = [false; 16];
+        row.iter().all(|y| *y < 16 && !std::mem::replace(&mut seen[*y as usize], true))
+    }
+
+    /// Rotates the lower `4 * W` bits to the left
+    pub fn rotate_left<const W: u32>(x: HalfBlock, n: u32) -> HalfBlock {
+        let bits = Width::<W>::HALF_BLOCK_BITS;
+
+        ((x << n) | (x >> (bits - n))) & Width::<W>::HALF_BLOCK_MASK
+    }
+
+    /// The substitution `t` of the full-size cipher
+    pub fn full_s_box(sbox: &FullSBox, x: FullHalfBlock) -> FullHalfBlock {
         let mut ret = 0;
-        for i in 0..4 {
-            let twice = i << 1;
-            ret |= SBOX[((x >> twice) & 0b11) as usize] << twice;
+        for (i, pi) in sbox.iter().enumerate() {
+            let shift = i << 2;
+            ret |= (pi[((x >> shift) & 0xf) as usize] as FullHalfBlock) << shift;
         }
 
         ret
     }
+
+    /// The round function `g[k]` of the full-size cipher
+    pub fn full_g(sbox: &FullSBox, key: FullRoundKey, x: FullHalfBlock) -> FullHalfBlock {
+        full_s_box(sbox, x.wrapping_add(key)).rotate_left(11)
+    }
+}
+
+impl CustomSBox {
+    pub fn sbox(&self) -> &FullSBox {
+        &self.0
+    }
+}
+
+impl ParamSet {
+    /// All the named parameter sets
+    pub const NAMED: [ParamSet; 7] = [
+        ParamSet::Toy,
+        ParamSet::Gost2015,
+        ParamSet::CryptoProA,
+        ParamSet::CryptoProB,
+        ParamSet::CryptoProC,
+        ParamSet::CryptoProD,
+        ParamSet::Test,
+    ];
+
+    /// Returns the parameter set with a user-supplied S-box.
+    /// Fails, if any of its rows is not a bijection.
+    pub fn custom(sbox: FullSBox) -> Result<ParamSet> {
+        match sbox.iter().position(|row| !utils::is_permutation(row)) {
+            Some(row) => Err(GostError::NonBijectiveSBox(row)),
+            None => Ok(ParamSet::Custom(CustomSBox(sbox))),
+        }
+    }
+
+    /// Returns the 4 bit S-boxes. The toy set falls back to GOST R 34.12-2015.
+    pub fn sbox(&self) -> &FullSBox {
+        match self {
+            ParamSet::Toy | ParamSet::Gost2015 => &FULL_SBOX,
+            ParamSet::CryptoProA => &CRYPTOPRO_A_SBOX,
+            ParamSet::CryptoProB => &CRYPTOPRO_B_SBOX,
+            ParamSet::CryptoProC => &CRYPTOPRO_C_SBOX,
+            ParamSet::CryptoProD => &CRYPTOPRO_D_SBOX,
+            ParamSet::Test => &TEST_SBOX,
+            ParamSet::Custom(custom) => custom.sbox(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParamSet::Toy => "toy",
+            ParamSet::Gost2015 => "gost2015",
+            ParamSet::CryptoProA => "cryptopro-a",
+            ParamSet::CryptoProB => "cryptopro-b",
+            ParamSet::CryptoProC => "cryptopro-c",
+            ParamSet::CryptoProD => "cryptopro-d",
+            ParamSet::Test => "test",
+            ParamSet::Custom(_) => "custom",
+        }
+    }
+}
+
+impl std::str::FromStr for ParamSet {
+    type Err = GostError;
+
+    /// Parses the name of a named parameter set
+    fn from_str(s: &str) -> Result<ParamSet> {
+        ParamSet::NAMED
+            .iter()
+            .find(|p| p.name() == s)
+            .cloned()
+            .ok_or_else(|| GostError::UnknownParamSet(s.to_string()))
+    }
+}
+
+impl<const W: u32> RoundTable<W> {
+    const CHUNKS: usize = Width::<W>::HALF_BLOCK_BITS.div_ceil(8) as usize;
+
+    /// Returns the table of the parameter set, every thread builds it once
+    pub fn new(params: &ParamSet) -> RoundTable<W> {
+        let chunks = ROUND_TABLES.with(|tables| {
+            tables
+                .borrow_mut()
+                .entry((W, *params))
+                .or_insert_with(|| Arc::new(Self::build(params)))
+                .clone()
+        });
+
+        RoundTable { chunks }
+    }
+
+    fn build(params: &ParamSet) -> ChunkTables {
+        let bits = Width::<W>::HALF_BLOCK_BITS.min(8);
+        let mut chunks = [[0; 256]; 4];
+
+        for (i, chunk) in chunks.iter_mut().enumerate().take(Self::CHUNKS) {
+            let shift = 8 * i as u32;
+            let mask = ((1 << bits) - 1) << shift;
+            for (x, y) in chunk.iter_mut().enumerate().take(1 << bits) {
+                // Only the output of the chunk itself, the S-boxes do not map zero to zero
+                let s = utils::s_box::<W>(params, (x as HalfBlock) << shift) & mask;
+                *y = utils::rotate_left::<W>(s, Width::<W>::ROTATION);
+            }
+        }
+
+        chunks
+    }
+
+    /// The same as `utils::g`
+    #[inline]
+    pub fn g(&self, x: HalfBlock) -> HalfBlock {
+        let mut y = 0;
+        for (i, chunk) in self.chunks.iter().enumerate().take(Self::CHUNKS) {
+            y ^= chunk[((x >> (8 * i)) & 0xff) as usize];
+        }
+
+        y
+    }
 }
 
-impl<'a> MagmaKey {
-    fn new(key: u64) -> MagmaKey {
-        MagmaKey { key }
+impl<'a, const W: u32> MagmaKey<W> {
+    fn new(key: State<W>) -> MagmaKey<W>
+    where
+        Width<W>: Storage,
+    {
+        let mut k = [0; 8];
+        for (i, sk) in k.iter_mut().enumerate() {
+            *sk = (key >> (i as u32 * Width::<W>::HALF_BLOCK_BITS)).low_u64() as RoundKey
+                & Width::<W>::HALF_BLOCK_MASK;
+        }
+
+        MagmaKey { key: k }
     }
 
-    fn scheduler(&'a self) -> MagmaKeyScheduler<'a> {
-        MagmaKeyScheduler::new(&self)
+    fn scheduler(&'a self, rounds: usize) -> MagmaKeyScheduler<'a, W> {
+        MagmaKeyScheduler::new(self, rounds)
     }
 }
 
-impl<'a> MagmaKeyScheduler<'a> {
-    fn new(key: &MagmaKey) -> MagmaKeyScheduler {
+impl<'a, const W: u32> MagmaKeyScheduler<'a, W> {
+    fn new(key: &MagmaKey<W>, rounds: usize) -> MagmaKeyScheduler<'_, W> {
         MagmaKeyScheduler {
             magma_key: key,
-            round_num: 0,
+            rounds,
+            front: 0,
+            back: rounds,
         }
     }
+
+    fn round_key(&self, round: usize) -> RoundKey {
+        self.magma_key.key[utils::subkey(self.rounds, round)]
+    }
 }
 
-impl<'a> Iterator for MagmaKeyScheduler<'a> {
-    type Item = HalfBlock;
+impl<'a, const W: u32> Iterator for MagmaKeyScheduler<'a, W> {
+    type Item = RoundKey;
 
-    //NOTE here the order might be broken. Try reordering hands of main
     fn next(&mut self) -> Option<Self::Item> {
-        let res = match self.round_num {
-            1..=24 => Some(
-                // (key >> ((round_num % 8) * 8))
-                (self.magma_key.key >> ((self.round_num & 0b111) << 3)) as u8,
-            ),
-            25..=32 => Some(
-                // reverse
-                (self.magma_key.key >> ((7 - (self.round_num & 0b111)) << 3)) as u8,
-            ),
-            _ => None,
-        };
-
-        self.round_num += 1;
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
 
-        res
+        Some(self.round_key(self.front - 1))
     }
 }
 
-impl<'a> DoubleEndedIterator for MagmaKeyScheduler<'a> {
+impl<'a, const W: u32> DoubleEndedIterator for MagmaKeyScheduler<'a, W> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        let res = match self.round_num {
-            1..=8 => Some(
-                ((self.magma_key.key >> (7 - (self.round_num & 0b111)) << 3) & 0b11111111) as u8,
-            ),
-            9..=32 => {
-                Some(((self.magma_key.key >> (self.round_num & 0b111) << 3) & 0b11111111) as u8)
-            }
-            _ => return None,
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+
+        Some(self.round_key(self.back))
+    }
+}
+
+impl<const W: u32> Magma<W> {
+    /// Returns a new instance of a block cipher. Takes a key of 32 "bytes" as an input.
+    pub fn new(key: State<W>) -> Magma<W>
+    where
+        Width<W>: Storage,
+    {
+        Self::with_params(key, ParamSet::Toy)
+    }
+
+    /// Returns a new instance of a block cipher, which uses the S-boxes of the given parameter set
+    pub fn with_params(key: State<W>, params: ParamSet) -> Magma<W>
+    where
+        Width<W>: Storage,
+    {
+        Self::with_rounds(key, params, ROUNDS)
+    }
+
+    /// Returns a new instance of a block cipher with the given number of rounds, see `MagmaKeyScheduler`
+    /// for the order of the round keys
+    pub fn with_rounds(key: State<W>, params: ParamSet, rounds: usize) -> Magma<W>
+    where
+        Width<W>: Storage,
+    {
+        let mut magma = Magma {
+            round_keys: Vec::with_capacity(rounds),
+            table: RoundTable::new(&params),
         };
+        magma.set_key(key, rounds);
 
-        self.round_num += 1;
+        magma
+    }
 
-        res
+    /// Returns a new instance of a block cipher, which takes the given round keys instead of deriving them
+    /// from a key, e.g. independent ones. There are as many rounds as the keys, only the lower `4 * W` bits
+    /// of the keys are used.
+    pub fn with_round_keys(round_keys: &[RoundKey], params: ParamSet) -> Magma<W> {
+        Magma {
+            round_keys: round_keys.iter().map(|k| k & Width::<W>::HALF_BLOCK_MASK).collect(),
+            table: RoundTable::new(&params),
+        }
     }
-}
 
-impl MagmaState {
-    fn new() -> MagmaState {
-        MagmaState {
-            left: 0u8,
-            right: 0u8,
+    /// Returns the round keys of `rounds` rounds, which the key gives, see `MagmaKeyScheduler`
+    pub fn expand_key(key: State<W>, rounds: usize) -> Vec<RoundKey>
+    where
+        Width<W>: Storage,
+    {
+        MagmaKey::<W>::new(key).scheduler(rounds).collect()
+    }
+
+    /// Returns the key, which gives the round keys, if there is one. It takes at least 8 rounds.
+    pub fn master_key(round_keys: &[RoundKey]) -> Option<State<W>>
+    where
+        Width<W>: Storage,
+    {
+        let rounds = round_keys.len();
+        if rounds < 8 {
+            return None;
+        }
+        // The last 8 rounds take sk7..sk0
+        let key = (0..8).fold(State::<W>::default(), |key, i| {
+            let sk = round_keys[rounds - 1 - i] & Width::<W>::HALF_BLOCK_MASK;
+            key | (State::<W>::from_u64(sk as u64) << (i as u32 * Width::<W>::HALF_BLOCK_BITS))
+        });
+
+        if Self::expand_key(key, rounds) == round_keys {
+            Some(key)
+        } else {
+            None
         }
     }
-}
 
-impl Magma {
-    /// Returns a new instance of a block cipher. Takes a key as an input.
-    /// # Panics
-    /// Panicks if the length of the key is not 32.
-    pub fn new(key: u64) -> Magma {
-        let key = MagmaKey::new(key);
-        let state = MagmaState::new();
+    pub fn round_keys(&self) -> &[RoundKey] {
+        &self.round_keys
+    }
 
-        Magma {
-            key: key,
-            state: state,
+    /// Replaces the key, keeping the S-boxes and the number of rounds.
+    /// This is cheaper than a new instance, when many keys are tried.
+    pub fn rekey(&mut self, key: State<W>)
+    where
+        Width<W>: Storage,
+    {
+        self.set_key(key, self.rounds());
+    }
+
+    /// Replaces the round keys of the range with the ones the key gives, keeping the others.
+    /// This is cheaper than `rekey`, when only these rounds are run with every key.
+    /// # Panics
+    /// Panics if the range goes beyond the rounds of the cipher.
+    pub fn rekey_rounds(&mut self, key: State<W>, rounds: Range<usize>)
+    where
+        Width<W>: Storage,
+    {
+        let key = MagmaKey::<W>::new(key);
+        let scheduler = key.scheduler(self.rounds());
+
+        for round in rounds {
+            self.round_keys[round] = scheduler.round_key(round);
         }
     }
 
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len()
+    }
+
     /// Encrypt a single block of plaintext
-    /// # Panics
-    /// Panics if the length of the block is not 8 bytes.
-    pub fn encrypt_block(&mut self, block: Block) -> Block {
-        self.state.left = (block & 0xff) as u8;
-        self.state.right = ((block & 0xff00) >> 8) as u8;
+    pub fn encrypt_block(&self, block: Block) -> Block {
+        Self::swap_halves(self.encrypt_rounds(block, 0..self.rounds()))
+    }
 
-        let key_scheduler = self.key.scheduler();
-        let left = &mut self.state.left;
-        let right = &mut self.state.right;
+    /// Decrypt a single block of plaintext
+    pub fn decrypt_block(&self, block: Block) -> Block {
+        // This undoes the swap of the halves after the last round
+        self.decrypt_rounds(Self::swap_halves(block), 0..self.rounds())
+    }
 
-        for round_key in key_scheduler {
-            Self::round(left, right, round_key);
+    /// Runs the rounds of the range, counting from zero, on the block. The block holds the left half
+    /// in the lower bits and the right one in the upper bits, both before and after the rounds,
+    /// so the ranges compose: the rounds `0..r` and then `r..n` are the same as `0..n`.
+    /// Unlike `encrypt_block`, the halves are not swapped after the last round.
+    /// # Panics
+    /// Panics if the range goes beyond the rounds of the cipher.
+    pub fn encrypt_rounds(&self, block: Block, rounds: Range<usize>) -> Block {
+        let (mut left, mut right) = Self::split_block(block);
+
+        for round_key in self.round_keys[rounds].iter() {
+            Self::round(&self.table, &mut left, &mut right, *round_key);
         }
 
-        ((*left as u16) << 8) | (*right as u16)
+        Self::join_block(left, right)
     }
 
-    /// Decrypt a single block of plaintext
+    /// Undoes `encrypt_rounds` with the same range
     /// # Panics
-    /// Panics if the length of the block is not 8 bytes.
-    pub fn decrypt_block(&mut self, block: Block) -> Block {
-        self.state.left = (block & 0xff) as u8;
-        self.state.right = ((block & 0xff00) >> 8) as u8;
+    /// Panics if the range goes beyond the rounds of the cipher.
+    pub fn decrypt_rounds(&self, block: Block, rounds: Range<usize>) -> Block {
+        // A round with swapped halves undoes itself
+        let (mut right, mut left) = Self::split_block(block);
 
-        let key_scheduler = self.key.scheduler().rev();
-        let left = &mut self.state.left;
-        let right = &mut self.state.right;
+        for round_key in self.round_keys[rounds].iter().rev() {
+            Self::round(&self.table, &mut left, &mut right, *round_key);
+        }
+
+        Self::join_block(right, left)
+    }
 
-        for round_key in key_scheduler {
-            Self::round(left, right, round_key);
+    /// Encrypts the blocks in place
+    pub fn encrypt_blocks(&self, blocks: &mut [Block]) {
+        for block in blocks.iter_mut() {
+            *block = self.encrypt_block(*block);
         }
+    }
 
-        // This order undos swaping on the last round
-        ((*left as u16) << 8) | (*right as u16)
+    /// Decrypts the blocks in place
+    pub fn decrypt_blocks(&self, blocks: &mut [Block]) {
+        for block in blocks.iter_mut() {
+            *block = self.decrypt_block(*block);
+        }
     }
 
     /// *Left* is lower bytes
-    /// *Right* is upper bytes 
-    pub fn round(left: &mut HalfBlock, right: &mut HalfBlock, key: RoundKey) {
-        *left ^= utils::s_box(right.wrapping_add(key)).rotate_left(3); 
+    /// *Right* is upper bytes
+    #[inline]
+    pub fn round(table: &RoundTable<W>, left: &mut HalfBlock, right: &mut HalfBlock, key: RoundKey) {
+        let x = right.wrapping_add(key) & Width::<W>::HALF_BLOCK_MASK;
+        *left ^= table.g(x);
         std::mem::swap(left, right);
     }
+
+    fn set_key(&mut self, key: State<W>, rounds: usize)
+    where
+        Width<W>: Storage,
+    {
+        self.round_keys.clear();
+        self.round_keys.extend(MagmaKey::<W>::new(key).scheduler(rounds));
+    }
+
+    /// Returns the left and the right halves
+    fn split_block(block: Block) -> (HalfBlock, HalfBlock) {
+        (
+            (block as HalfBlock) & Width::<W>::HALF_BLOCK_MASK,
+            ((block >> Width::<W>::HALF_BLOCK_BITS) as HalfBlock) & Width::<W>::HALF_BLOCK_MASK,
+        )
+    }
+
+    fn join_block(left: HalfBlock, right: HalfBlock) -> Block {
+        ((right as Block) << Width::<W>::HALF_BLOCK_BITS) | left as Block
+    }
+
+    fn swap_halves(block: Block) -> Block {
+        let (left, right) = Self::split_block(block);
+
+        Self::join_block(right, left)
+    }
+}
+
+impl FullMagma {
+    /// Returns a new instance of the full-size block cipher. The key is given in the byte order
+    /// of the standard, i.e. `key[0..4]` is the first round key K1.
+    pub fn new(key: &FullKey) -> FullMagma {
+        let mut k = [0; 8];
+        for (dst, src) in k.iter_mut().zip(key.chunks(4)) {
+            *dst = FullRoundKey::from_be_bytes([src[0], src[1], src[2], src[3]]);
+        }
+
+        Self::with_sbox(k, FULL_SBOX)
+    }
+
+    /// Returns a new instance of GOST 28147-89 with the given S-boxes.
+    /// Takes the key as eight 32 bit words K1..K8.
+    pub fn with_sbox(k: [FullRoundKey; 8], sbox: FullSBox) -> FullMagma {
+        let mut round_keys = [0; 32];
+        for (i, rk) in round_keys.iter_mut().enumerate() {
+            // K1..K8 three times, then K8..K1
+            *rk = if i < 24 { k[i & 0b111] } else { k[7 - (i & 0b111)] };
+        }
+
+        FullMagma { round_keys, sbox }
+    }
+
+    /// Encrypt a single block of plaintext. The upper half of the block is `a1`, the lower one is `a0`.
+    pub fn encrypt_block(&self, block: FullBlock) -> FullBlock {
+        self.transform(block, self.round_keys.iter())
+    }
+
+    /// Decrypt a single block of ciphertext
+    pub fn decrypt_block(&self, block: FullBlock) -> FullBlock {
+        self.transform(block, self.round_keys.iter().rev())
+    }
+
+    /// Transformation `G[k]`: (a1, a0) -> (a0, g[k](a0) xor a1)
+    pub fn round(&self, a1: &mut FullHalfBlock, a0: &mut FullHalfBlock, key: FullRoundKey) {
+        *a1 ^= utils::full_g(&self.sbox, key, *a0);
+        std::mem::swap(a1, a0);
+    }
+
+    fn transform<'a, I>(&self, block: FullBlock, round_keys: I) -> FullBlock
+    where
+        I: Iterator<Item = &'a FullRoundKey>,
+    {
+        let mut a1 = (block >> 32) as FullHalfBlock;
+        let mut a0 = block as FullHalfBlock;
+
+        for k in round_keys {
+            self.round(&mut a1, &mut a0, *k);
+        }
+
+        // The last round G* does not swap the halves
+        ((a0 as FullBlock) << 32) | a1 as FullBlock
+    }
 }
 
 #[cfg(test)]
@@ -178,7 +692,7 @@ mod test {
         let key = 0b0001101100011011000110110001101100011011000110110001101111100100;
         let block = 0b0110110000011110;
 
-        let mut magma = super::Magma::new(key);
+        let magma = super::Magma::<2>::new(key);
         let ecrypted = magma.encrypt_block(block);
 
         let decrypted = magma.decrypt_block(ecrypted);
@@ -191,7 +705,7 @@ mod test {
 
     #[test]
     fn key_schedule_test() {
-        let key = super::MagmaKey::new(0b0001101100011011000110110001101100011011000110110001101111100100);
+        let key = super::MagmaKey::<2>::new(0b0001101100011011000110110001101100011011000110110001101111100100);
         let expected = [
             0b11100100, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011,
             0b11100100, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011,
@@ -199,16 +713,17 @@ mod test {
             0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b11100100,
         ];
 
-        let key_schedule = super::MagmaKeyScheduler::new(&key);
+        let key_schedule = super::MagmaKeyScheduler::new(&key, 32);
 
         for (i, roundkey) in key_schedule.enumerate() {
             assert_eq!(roundkey, expected[i], "Key schedule is broken");
         }
+        assert_eq!(super::MagmaKeyScheduler::new(&key, 32).count(), 32, "Key schedule is broken");
     }
 
     #[test]
     fn key_schedule_rev_test() {
-        let key = super::MagmaKey::new(0b0001101100011011000110110001101100011011000110110001101111100100);
+        let key = super::MagmaKey::<2>::new(0b0001101100011011000110110001101100011011000110110001101111100100);
         let expected = [
             0b11100100, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011,
             0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b11100100,
@@ -216,20 +731,254 @@ mod test {
             0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b00011011, 0b11100100,
         ];
 
-        let key_schedule = super::MagmaKeyScheduler::new(&key).rev();
+        let key_schedule = super::MagmaKeyScheduler::new(&key, 32).rev();
 
         for (i, roundkey) in key_schedule.enumerate() {
             assert_eq!(roundkey, expected[i], "Key schedule is broken");
         }
     }
 
+    #[test]
+    fn round_ranges() {
+        let key = 0x1b1b_1b1b_e4e4_e4e4;
+        let mut magma = super::Magma::<2>::with_params(!key, super::ParamSet::CryptoProB);
+        magma.rekey(key);
+        let reference = super::Magma::<2>::with_params(key, super::ParamSet::CryptoProB);
+
+        for block in (0..1 << 16).step_by(97) {
+            let middle = magma.encrypt_rounds(block, 0..13);
+            let end = magma.encrypt_rounds(middle, 13..32);
+            assert_eq!(((end & 0xff) << 8) | end >> 8, reference.encrypt_block(block), "Rounds do not compose");
+            assert_eq!(magma.decrypt_rounds(end, 13..32), middle);
+            assert_eq!(magma.decrypt_rounds(middle, 0..13), block);
+        }
+
+        // 16 rounds: sk0..sk7, then sk7..sk0
+        let magma = super::Magma::<2>::with_rounds(key, super::ParamSet::Toy, 16);
+        let full = super::Magma::<2>::with_params(key, super::ParamSet::Toy);
+        assert_eq!(magma.rounds(), 16);
+        for block in (0..1 << 16).step_by(101) {
+            let expected = full.encrypt_rounds(full.encrypt_rounds(block, 0..8), 24..32);
+            assert_eq!(magma.encrypt_rounds(block, 0..16), expected);
+            assert_eq!(magma.decrypt_block(magma.encrypt_block(block)), block);
+        }
+    }
+
+    #[test]
+    fn independent_round_keys() {
+        let key = 0x1b1b_1b1b_e4e4_e4e4;
+        let schedule = super::Magma::<2>::expand_key(key, 32);
+        assert_eq!(super::Magma::<2>::master_key(&schedule), Some(key));
+
+        let expanded = super::Magma::<2>::with_round_keys(&schedule, super::ParamSet::Toy);
+        let derived = super::Magma::<2>::new(key);
+        assert_eq!(expanded.round_keys(), derived.round_keys());
+        for block in (0..1 << 16).step_by(89) {
+            assert_eq!(expanded.encrypt_block(block), derived.encrypt_block(block));
+        }
+
+        let independent: Vec<u32> = (0..20).map(|i| i * 37 % 256).collect();
+        assert_eq!(super::Magma::<2>::master_key(&independent), None);
+        assert_eq!(super::Magma::<2>::master_key(&independent[..5]), None);
+        let magma = super::Magma::<2>::with_round_keys(&independent, super::ParamSet::CryptoProD);
+        assert_eq!(magma.rounds(), 20);
+        for block in (0..1 << 16).step_by(89) {
+            assert_eq!(magma.decrypt_block(magma.encrypt_block(block)), block);
+        }
+        assert_eq!(super::Magma::<1>::with_round_keys(&[0xff; 8], super::ParamSet::Toy).round_keys(), &[0xf; 8]);
+
+        // The rounds 5..8 alone, as the seekers run them
+        let mut partial = super::Magma::<2>::new(0);
+        partial.rekey_rounds(key, 4..8);
+        assert_eq!(partial.round_keys()[4..8], schedule[4..8]);
+        assert_eq!(partial.round_keys()[..4], [0; 4]);
+        for block in (0..1 << 16).step_by(89) {
+            assert_eq!(partial.decrypt_rounds(block, 4..8), derived.decrypt_rounds(block, 4..8));
+        }
+    }
+
+    #[test]
+    fn toy_key_schedule_regression() {
+        // The scheduler used to yield no round keys at all, so the cipher only swapped the halves
+        let pinned = [(0x1b1b_1b1b_e4e4_e4e4, 0x6c1e, 0xe2be), (0x0123_4567_89ab_cdef, 0xa5f0, 0xf85c)];
+
+        for (key, block, expected) in pinned.iter() {
+            let encrypted = super::Magma::<2>::new(*key).encrypt_block(*block);
+            assert_eq!(encrypted, *expected, "The toy outputs have changed");
+            assert_ne!(encrypted, (block >> 8) | ((block & 0xff) << 8), "The round keys are lost");
+        }
+    }
+
     #[test]
     fn sbox_test() {
         let x = 0b00011011;
         let expected = 0b01110010;
 
-        let res = super::utils::s_box(x);
+        let res = super::utils::s_box::<2>(&super::ParamSet::Toy, x);
 
         assert_eq!(res, expected, "SBOX transformation does not work.");
     }
+
+    fn round_table<const W: u32>() {
+        let step = (crate::width::Width::<W>::HALF_BLOCK_MASK / 4096).max(1);
+        let mut params: Vec<_> = super::ParamSet::NAMED.to_vec();
+        params.push(super::ParamSet::custom([[7, 2, 15, 0, 9, 4, 11, 1, 14, 3, 12, 5, 10, 6, 13, 8]; 8]).unwrap());
+
+        for params in params.iter() {
+            let table = super::RoundTable::<W>::new(params);
+            for x in (0..=crate::width::Width::<W>::HALF_BLOCK_MASK).step_by(step as usize) {
+                assert_eq!(table.g(x), super::utils::g::<W>(params, x), "{} at {:#x}", params.name(), x);
+            }
+        }
+    }
+
+    #[test]
+    fn round_table_all_widths() {
+        round_table::<1>();
+        round_table::<2>();
+        round_table::<4>();
+        round_table::<8>();
+    }
+
+    fn roundtrip<const W: u32>(key: crate::width::State<W>)
+    where
+        crate::width::Width<W>: crate::width::Storage,
+    {
+        for params in super::ParamSet::NAMED.iter() {
+            roundtrip_with::<W>(key, *params);
+        }
+    }
+
+    fn roundtrip_with<const W: u32>(key: crate::width::State<W>, params: super::ParamSet)
+    where
+        crate::width::Width<W>: crate::width::Storage,
+    {
+        let magma = super::Magma::<W>::with_params(key, params);
+
+        for i in 0..1024u64 {
+            let block = i.wrapping_mul(0x9e37_79b9_7f4a_7c15) & crate::width::Width::<W>::BLOCK_MASK;
+            let ecrypted = magma.encrypt_block(block);
+            assert!(ecrypted <= crate::width::Width::<W>::BLOCK_MASK, "Ciphertext is too long");
+            assert_eq!(magma.decrypt_block(ecrypted), block, "Initial and decrypted blocks does not match.");
+        }
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let magma = super::Magma::<2>::with_params(0x1b1b_1b1b_e4e4_e4e4, super::ParamSet::CryptoProA);
+        let blocks: Vec<u64> = (0..1 << 16).collect();
+        let mut encrypted = blocks.clone();
+        magma.encrypt_blocks(&mut encrypted);
+
+        std::thread::scope(|scope| {
+            for (blocks, encrypted) in blocks.chunks(1 << 14).zip(encrypted.chunks(1 << 14)) {
+                let magma = &magma;
+                scope.spawn(move || {
+                    for (block, expected) in blocks.iter().zip(encrypted.iter()) {
+                        assert_eq!(magma.encrypt_block(*block), *expected);
+                    }
+                    let mut decrypted = encrypted.to_vec();
+                    magma.decrypt_blocks(&mut decrypted);
+                    assert_eq!(decrypted, blocks);
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn ecryption_decryption_all_widths() {
+        use crate::width::{Word, U256};
+
+        roundtrip::<1>(0x1b2e_4d78);
+        roundtrip::<2>(0x1b2e_4d78_9abc_def0);
+        roundtrip::<4>(0x1b2e_4d78_9abc_def0_0fed_cba9_8765_4321);
+        roundtrip::<8>(U256::from_limbs(&[0x1b2e_4d78, 0x9abc_def0, 0x0fed_cba9, 0x8765_4321]));
+    }
+
+    #[test]
+    fn sbox_8_bit_bytes_is_full_sbox() {
+        let x = 0xfdb97531;
+
+        assert_eq!(super::utils::s_box::<8>(&super::ParamSet::Toy, x), super::utils::full_s_box(&super::FULL_SBOX, x));
+        for params in super::ParamSet::NAMED.iter() {
+            assert_eq!(super::utils::s_box::<8>(params, x), super::utils::full_s_box(params.sbox(), x));
+        }
+    }
+
+    #[test]
+    fn param_sets() {
+        let mut sbox = *super::ParamSet::CryptoProA.sbox();
+
+        for params in super::ParamSet::NAMED.iter() {
+            assert!(
+                params.sbox().iter().all(super::utils::is_permutation),
+                "S-box of {} is not a bijection",
+                params.name()
+            );
+            assert_eq!(params.name().parse::<super::ParamSet>(), Ok(*params));
+        }
+        assert_eq!(super::ParamSet::custom(sbox), Ok(super::ParamSet::Custom(super::CustomSBox(sbox))));
+
+        sbox[3][5] = sbox[3][6];
+        assert_eq!(super::ParamSet::custom(sbox), Err(super::GostError::NonBijectiveSBox(3)));
+        sbox[3][5] = 16;
+        assert_eq!(super::ParamSet::custom(sbox), Err(super::GostError::NonBijectiveSBox(3)));
+        assert_eq!(
+            "cryptopro-e".parse::<super::ParamSet>(),
+            Err(super::GostError::UnknownParamSet("cryptopro-e".to_string()))
+        );
+    }
+
+    #[test]
+    fn cryptopro_a_test_vector() {
+        let key: Vec<u8> = (0..32).map(|i| i * 7 + 1).collect();
+        let mut k = [0; 8];
+        for (dst, src) in k.iter_mut().zip(key.chunks(4)) {
+            *dst = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        }
+        let block = u64::from_le_bytes([0x10, 0x32, 0x54, 0x76, 0x98, 0xba, 0xdc, 0xfe]);
+
+        let magma = super::FullMagma::with_sbox(k, *super::ParamSet::CryptoProA.sbox());
+        let encrypted = magma.encrypt_block(block);
+        assert_eq!(encrypted, u64::from_le_bytes([0xda, 0x27, 0xe6, 0xb2, 0xb9, 0xdf, 0x09, 0x94]));
+        assert_eq!(magma.decrypt_block(encrypted), block);
+
+        let magma = super::FullMagma::with_sbox(k, *super::ParamSet::Test.sbox());
+        assert_eq!(magma.encrypt_block(block), u64::from_le_bytes([0x57, 0x85, 0x94, 0xcc, 0x50, 0xf3, 0x21, 0x22]));
+    }
+
+    // Test vectors from GOST R 34.12-2015, section A.2
+    #[test]
+    fn full_t_test() {
+        let x = [0xfdb97531, 0x2a196f34, 0xebd9f03a, 0xb039bb3d, 0x68695433];
+
+        for w in x.windows(2) {
+            assert_eq!(super::utils::full_s_box(&super::FULL_SBOX, w[0]), w[1], "Transformation t is broken");
+        }
+    }
+
+    #[test]
+    fn full_g_test() {
+        // g[x_i](x_i-1) = x_i+1
+        let x = [0xfedcba98, 0x87654321, 0xfdcbc20c, 0x7e791a4b, 0xc76549ec, 0x9791c849];
+
+        for w in x.windows(3) {
+            assert_eq!(super::utils::full_g(&super::FULL_SBOX, w[1], w[0]), w[2], "Transformation g is broken");
+        }
+    }
+
+    #[test]
+    fn full_ecryption_decryption_test() {
+        let key = [
+            0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+            0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+        ];
+        let magma = super::FullMagma::new(&key);
+
+        assert_eq!(magma.round_keys[..8], [
+            0xffeeddcc, 0xbbaa9988, 0x77665544, 0x33221100, 0xf0f1f2f3, 0xf4f5f6f7, 0xf8f9fafb, 0xfcfdfeff,
+        ]);
+        assert_eq!(magma.encrypt_block(0xfedcba9876543210), 0x4ee901e5c2d8ca3d, "Encryption is broken");
+        assert_eq!(magma.decrypt_block(0x4ee901e5c2d8ca3d), 0xfedcba9876543210, "Decryption is broken");
+    }
 }
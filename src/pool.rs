@@ -0,0 +1,103 @@
+//! # Worker pool
+//! The seekers run on a fixed set of threads, which live as long as the attack, so a round does not
+//! spawn new threads. Every call of `WorkerPool::run` gives one job to each of the workers and waits
+//! for all of them.
+use crate::error::{GostError, Result};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+pub struct WorkerPool {
+    jobs: Vec<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    /// Starts `threads` workers, at least one
+    pub fn new(threads: usize) -> WorkerPool {
+        let (jobs, workers) = (0..threads.max(1))
+            .map(|_| {
+                let (tx, rx) = mpsc::channel::<Job>();
+                let worker = thread::spawn(move || {
+                    for job in rx {
+                        job();
+                    }
+                });
+                (tx, worker)
+            })
+            .unzip();
+
+        WorkerPool { jobs, workers }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Runs `job(i)` on the `i`-th worker for every worker and returns the results in the order of the workers.
+    /// Waits for all the jobs, even if some of them have failed. A panicking job gives `GostError::Poisoned`,
+    /// the worker survives it.
+    pub fn run<T, F>(&self, job: F) -> Result<Vec<T>>
+    where
+        T: Send + 'static,
+        F: Fn(usize) -> Result<T> + Send + Sync + 'static,
+    {
+        let job = Arc::new(job);
+        let (tx, rx) = mpsc::channel();
+
+        for (i, worker) in self.jobs.iter().enumerate() {
+            let job = job.clone();
+            let tx = tx.clone();
+            let sent = worker.send(Box::new(move || {
+                let res = panic::catch_unwind(AssertUnwindSafe(|| job(i))).unwrap_or(Err(GostError::Poisoned));
+                // The receiver is only gone, if `run` itself has panicked
+                let _ = tx.send((i, res));
+            }));
+            if sent.is_err() {
+                return Err(GostError::Poisoned);
+            }
+        }
+        drop(tx);
+
+        let mut results: Vec<Option<Result<T>>> = (0..self.threads()).map(|_| None).collect();
+        for (i, res) in rx {
+            results[i] = Some(res);
+        }
+
+        results.into_iter().map(|res| res.unwrap_or(Err(GostError::Poisoned))).collect()
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // The workers stop, once their channels are closed
+        self.jobs.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WorkerPool;
+    use crate::error::GostError;
+
+    #[test]
+    fn jobs_run_in_order() {
+        let pool = WorkerPool::new(4);
+
+        for round in 0..3 {
+            assert_eq!(pool.run(move |i| Ok(i * round)), Ok(vec![0, round, 2 * round, 3 * round]));
+        }
+        assert_eq!(
+            pool.run(|i| if i == 2 { panic!("A failing seeker") } else { Ok(i) }),
+            Err(GostError::Poisoned)
+        );
+        assert_eq!(pool.run(Ok), Ok(vec![0, 1, 2, 3]), "The workers should survive the panic");
+        assert_eq!(WorkerPool::new(0).threads(), 1);
+    }
+}
@@ -0,0 +1,163 @@
+//! # Progress
+//! The attack reports its progress through `ProgressSink`, so the library does not draw anything itself.
+//! `NoProgress` ignores the events, `BatchedReporter` sums them up and passes a `Report` to a callback
+//! once in a while. The progress bars live in the binary.
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Phases of the attack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Collecting the fixed points, round after round
+    FixedPoints,
+    /// Encrypting h with all the solutions for sk0..sk3
+    Forward,
+    /// Decrypting the swapped h with all the solutions for sk4..sk7
+    Backward,
+}
+
+/// Receives the events of the attack. The keys are probed by several threads at once, so the sink
+/// is shared between them. All the events are ignored by default.
+pub trait ProgressSink: Send + Sync {
+    /// The phase starts, `total` is the number of keys or the number of fixed points to find
+    fn phase_start(&self, _phase: Phase, _total: u64) {}
+
+    fn phase_end(&self, _phase: Phase) {}
+
+    /// `n` more keys have been probed. The seekers report in batches, not key by key.
+    fn keys_probed(&self, _phase: Phase, _n: u64) {}
+
+    /// `total` fixed points are known by now
+    fn fixed_points_found(&self, _total: u64) {}
+
+    fn collision_found(&self) {}
+}
+
+/// Ignores all the events
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {}
+
+/// The progress summed up by `BatchedReporter`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The running phase of the key search, if any
+    pub phase: Option<Phase>,
+    pub keys_probed: u64,
+    pub keys_total: u64,
+    pub fixed_points: u64,
+    pub fixed_points_target: u64,
+    pub collision: bool,
+}
+
+/// Passes the report to the callback at most once per `interval`, and always when
+/// a phase ends or the collision is found
+pub struct BatchedReporter<F>
+where
+    F: Fn(&Report) + Send + Sync,
+{
+    interval: Duration,
+    callback: F,
+    state: Mutex<(Report, Instant)>,
+}
+
+impl<F> BatchedReporter<F>
+where
+    F: Fn(&Report) + Send + Sync,
+{
+    pub fn new(interval: Duration, callback: F) -> BatchedReporter<F> {
+        BatchedReporter {
+            interval,
+            callback,
+            state: Mutex::new((Report::default(), Instant::now())),
+        }
+    }
+
+    /// Updates the report and passes it on, if it is time or `force` is set
+    fn update<U: FnOnce(&mut Report)>(&self, force: bool, update: U) {
+        // The report is plain data, so a poisoned lock still holds a usable one
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        update(&mut state.0);
+
+        if force || state.1.elapsed() >= self.interval {
+            state.1 = Instant::now();
+            (self.callback)(&state.0);
+        }
+    }
+}
+
+impl<F> ProgressSink for BatchedReporter<F>
+where
+    F: Fn(&Report) + Send + Sync,
+{
+    fn phase_start(&self, phase: Phase, total: u64) {
+        self.update(false, |r| match phase {
+            Phase::FixedPoints => r.fixed_points_target = total,
+            _ => {
+                r.phase = Some(phase);
+                r.keys_probed = 0;
+                r.keys_total = total;
+            }
+        });
+    }
+
+    fn phase_end(&self, phase: Phase) {
+        self.update(true, |r| {
+            if r.phase == Some(phase) {
+                r.phase = None;
+            }
+        });
+    }
+
+    fn keys_probed(&self, _phase: Phase, n: u64) {
+        self.update(false, |r| r.keys_probed += n);
+    }
+
+    fn fixed_points_found(&self, total: u64) {
+        self.update(false, |r| r.fixed_points = total);
+    }
+
+    fn collision_found(&self) {
+        self.update(true, |r| r.collision = true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BatchedReporter, Phase, ProgressSink, Report};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn batched_reporter() {
+        let reports = Mutex::new(Vec::new());
+        let reporter = BatchedReporter::new(Duration::from_secs(3600), |r: &Report| {
+            reports.lock().unwrap().push(r.clone())
+        });
+
+        reporter.phase_start(Phase::FixedPoints, 1 << 24);
+        reporter.phase_start(Phase::Forward, 100);
+        for _ in 0..10 {
+            reporter.keys_probed(Phase::Forward, 10);
+        }
+        reporter.phase_end(Phase::Forward);
+        reporter.fixed_points_found(5);
+        reporter.collision_found();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 2, "Only the forced reports are expected");
+        assert_eq!(reports[0].keys_probed, 100);
+        assert_eq!(reports[0].phase, None);
+        assert_eq!(
+            reports[1],
+            Report {
+                phase: None,
+                keys_probed: 100,
+                keys_total: 100,
+                fixed_points: 5,
+                fixed_points_target: 1 << 24,
+                collision: true,
+            }
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! # Width of a "byte"
+//! The GOST primitives in this crate work on "bytes" of `W` bits, where `W` is 1, 2, 4 or 8.
+//! A block of Magma is always 8 "bytes" and the hash state is always 32 "bytes", so `W` fixes
+//! all the other sizes. `Width<W>` maps the width to the integer types which hold the data.
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
+
+/// Marker type for the width of a "byte" in bits
+pub struct Width<const W: u32>;
+
+/// An unsigned integer, which holds a fixed number of "bytes"
+pub trait Word:
+    Copy
+    + Default
+    + Eq
+    + Ord
+    + Hash
+    + Debug
+    + Send
+    + Sync
+    + 'static
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+    + BitXorAssign
+{
+    const BITS: u32;
+
+    fn from_u64(x: u64) -> Self;
+
+    /// Returns the lowest 64 bits
+    fn low_u64(self) -> u64;
+
+    fn wrapping_add(self, other: Self) -> Self;
+
+    /// Returns the little endian 64 bit limbs, the missing ones are zero
+    fn to_limbs(self) -> [u64; 4];
+
+    /// Takes the little endian 64 bit limbs, the excessive bits are dropped
+    fn from_limbs(limbs: &[u64]) -> Self;
+
+    /// Returns a word with the lower `bits` bits set
+    fn mask(bits: u32) -> Self {
+        if bits >= Self::BITS {
+            !Self::default()
+        } else {
+            !(!Self::default() << bits)
+        }
+    }
+}
+
+/// Integer types for a given width
+pub trait Storage {
+    /// 32 "bytes": the hash state, a message block and a key of Magma
+    type State: Word;
+    /// 16 "bytes": a half of the key of Magma, sk0..sk3 or sk4..sk7
+    type HalfKey: Word;
+}
+
+pub type State<const W: u32> = <Width<W> as Storage>::State;
+pub type HalfKey<const W: u32> = <Width<W> as Storage>::HalfKey;
+
+impl Storage for Width<1> {
+    type State = u32;
+    type HalfKey = u16;
+}
+
+impl Storage for Width<2> {
+    type State = u64;
+    type HalfKey = u32;
+}
+
+impl Storage for Width<4> {
+    type State = u128;
+    type HalfKey = u64;
+}
+
+impl Storage for Width<8> {
+    type State = U256;
+    type HalfKey = u128;
+}
+
+impl<const W: u32> Width<W> {
+    pub const BYTE_MASK: u8 = (0xffu16 >> (8 - W)) as u8;
+    /// A half of a Magma block, the size of a round key
+    pub const HALF_BLOCK_BITS: u32 = 4 * W;
+    pub const HALF_BLOCK_MASK: u32 = (!0u32) >> (32 - 4 * W);
+    pub const BLOCK_BITS: u32 = 8 * W;
+    pub const BLOCK_MASK: u64 = (!0u64) >> (64 - 8 * W);
+    pub const STATE_BITS: u32 = 32 * W;
+    /// The toy S-boxes of Magma act on chunks of `min(W, 4)` bits
+    pub const SBOX_BITS: u32 = if W < 4 { W } else { 4 };
+    /// The rotation of Magma round function, scaled from 11 out of 32 bits
+    pub const ROTATION: u32 = (44 * W + 16) / 32;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn from_u64(x: u64) -> Self {
+                    x as $t
+                }
+
+                fn low_u64(self) -> u64 {
+                    self as u64
+                }
+
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$t>::wrapping_add(self, other)
+                }
+
+                #[allow(clippy::unnecessary_cast)]
+                fn to_limbs(self) -> [u64; 4] {
+                    let mut limbs = [0; 4];
+                    for (i, l) in limbs.iter_mut().enumerate().take(<$t>::BITS.div_ceil(64) as usize) {
+                        *l = (self as u128 >> (i << 6)) as u64;
+                    }
+
+                    limbs
+                }
+
+                fn from_limbs(limbs: &[u64]) -> Self {
+                    let mut x = 0u128;
+                    for (i, l) in limbs.iter().enumerate().take(<$t>::BITS.div_ceil(64) as usize) {
+                        x |= (*l as u128) << (i << 6);
+                    }
+
+                    x as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(u16, u32, u64, u128);
+
+/// 256 bit unsigned integer with little endian limbs, the hash state for 8 bit bytes
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct U256([u64; 4]);
+
+impl Word for U256 {
+    const BITS: u32 = 256;
+
+    fn from_u64(x: u64) -> Self {
+        U256([x, 0, 0, 0])
+    }
+
+    fn low_u64(self) -> u64 {
+        self.0[0]
+    }
+
+    fn wrapping_add(self, other: Self) -> Self {
+        let mut res = [0; 4];
+        let mut carry = false;
+        for (i, r) in res.iter_mut().enumerate() {
+            let (s, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (s, c2) = s.overflowing_add(carry as u64);
+            *r = s;
+            carry = c1 || c2;
+        }
+
+        U256(res)
+    }
+
+    fn to_limbs(self) -> [u64; 4] {
+        self.0
+    }
+
+    fn from_limbs(limbs: &[u64]) -> Self {
+        let mut res = [0; 4];
+        for (dst, src) in res.iter_mut().zip(limbs.iter()) {
+            *dst = *src;
+        }
+
+        U256(res)
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl Not for U256 {
+    type Output = U256;
+
+    fn not(self) -> U256 {
+        U256([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+macro_rules! impl_u256_bitop {
+    ($tr:ident, $f:ident, $tr_assign:ident, $f_assign:ident, $op:tt) => {
+        impl $tr for U256 {
+            type Output = U256;
+
+            fn $f(mut self, rhs: U256) -> U256 {
+                self $op rhs;
+                self
+            }
+        }
+
+        impl $tr_assign for U256 {
+            fn $f_assign(&mut self, rhs: U256) {
+                for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+                    *a $op b;
+                }
+            }
+        }
+    };
+}
+
+impl_u256_bitop!(BitAnd, bitand, BitAndAssign, bitand_assign, &=);
+impl_u256_bitop!(BitOr, bitor, BitOrAssign, bitor_assign, |=);
+impl_u256_bitop!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^=);
+
+impl Shl<u32> for U256 {
+    type Output = U256;
+
+    /// # Panics
+    /// Panics if `n` is 256 or more, as the primitive types do.
+    fn shl(self, n: u32) -> U256 {
+        assert!(n < 256, "attempt to shift left with overflow");
+        let limbs = (n >> 6) as usize;
+        let bits = n & 63;
+        let mut res = [0; 4];
+
+        for (i, r) in res.iter_mut().enumerate().skip(limbs) {
+            *r = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                *r |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+
+        U256(res)
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = U256;
+
+    /// # Panics
+    /// Panics if `n` is 256 or more, as the primitive types do.
+    fn shr(self, n: u32) -> U256 {
+        assert!(n < 256, "attempt to shift right with overflow");
+        let limbs = (n >> 6) as usize;
+        let bits = n & 63;
+        let mut res = [0; 4];
+
+        for (i, r) in res.iter_mut().enumerate().take(4 - limbs) {
+            *r = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                *r |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+
+        U256(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Word, U256};
+
+    #[test]
+    fn u256_matches_u128() {
+        let x: u128 = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+        let y: u128 = 0xdead_beef_0000_ffff_1234_5678_9abc_def0;
+        let (ux, uy) = (U256::from_limbs(&x.to_limbs()), U256::from_limbs(&y.to_limbs()));
+
+        for n in [0, 1, 17, 63, 64, 65, 100, 127].iter() {
+            assert_eq!((ux << *n).to_limbs()[..2], (x << *n).to_limbs()[..2]);
+            assert_eq!((ux >> *n).to_limbs(), (x >> *n).to_limbs());
+        }
+        assert_eq!((ux ^ uy).to_limbs(), (x ^ y).to_limbs());
+        assert_eq!(ux.wrapping_add(uy).to_limbs()[..2], x.wrapping_add(y).to_limbs()[..2]);
+        assert_eq!(ux.cmp(&uy), x.cmp(&y));
+    }
+
+    #[test]
+    fn u256_carries() {
+        let max = !U256::default();
+
+        assert_eq!(max.wrapping_add(U256::from_u64(1)), U256::default());
+        assert_eq!((U256::from_u64(1) << 255) >> 255, U256::from_u64(1));
+        assert_eq!(U256::mask(130).to_limbs(), [!0, !0, 0b11, 0]);
+    }
+}